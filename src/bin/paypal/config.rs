@@ -0,0 +1,20 @@
+use paypal::Credentials;
+use std::path::{Path, PathBuf};
+
+// where credentials are read from when `--config` is not given, if the file exists
+const DEFAULT_PATH: &str = ".config/paypal/credentials";
+
+// an explicitly given file must exist, the default one is optional
+pub fn credentials(path: Option<&Path>) -> Result<Credentials, paypal::error::Error> {
+    match path {
+        Some(path) => Credentials::from_env_or_file(path),
+        None => match default_path() {
+            Some(path) if path.exists() => Credentials::from_env_or_file(path),
+            _ => Credentials::from_env(),
+        },
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| Path::new(&home).join(DEFAULT_PATH))
+}
@@ -0,0 +1,501 @@
+//! The `paypal` command-line tool, for one-off operations on PayPal from the terminal.
+//!
+//! Credentials are read from the `PAYPAL_CLIENT_ID` and `PAYPAL_SECRET` environment variables,
+//! or from a file of `KEY=value` lines, `~/.config/paypal/credentials` unless `--config` says
+//! otherwise. Environment variables win over the file.
+//!
+//! Besides tokens and v1 payments, the tool creates, shows and captures v2 orders, refunds
+//! captures and sales, verifies webhook signatures and searches transactions.
+
+mod config;
+mod output;
+
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use output::{Format, Table};
+use paypal::{capture, order, payment, sale, transaction, webhook};
+use paypal::{Client, Money, Order, OrderIntent, OrderRequest, Payment, PurchaseUnit};
+use paypal::{TransactionAmount, TransactionSearch, VerificationStatus, WebhookSignature};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const LIVE_URL: &str = "https://api.paypal.com";
+const SANDBOX_URL: &str = "https://api.sandbox.paypal.com";
+
+#[derive(Parser)]
+#[command(
+    name = "paypal",
+    version,
+    about = "Operate on PayPal from the terminal"
+)]
+struct Cli {
+    /// File to read credentials from, as KEY=value lines
+    #[arg(long, global = true, env = "PAYPAL_CONFIG")]
+    config: Option<PathBuf>,
+    /// Use PayPal's sandbox instead of the live API
+    #[arg(long, global = true, conflicts_with = "base_url")]
+    sandbox: bool,
+    /// The API to talk to, when neither the live one nor the sandbox
+    #[arg(long, global = true, env = "PAYPAL_BASE_URL")]
+    base_url: Option<String>,
+    /// How to print results
+    #[arg(long, short, global = true, value_enum, default_value_t = Format::Table)]
+    output: Format,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints a new access token
+    Token,
+    /// Shows, lists and executes payments
+    #[command(subcommand)]
+    Payment(PaymentCommand),
+    /// Creates, shows and captures v2 orders
+    #[command(subcommand)]
+    Order(OrderCommand),
+    /// Refunds captures and sales
+    #[command(subcommand)]
+    Refund(RefundCommand),
+    /// Verifies webhook events
+    #[command(subcommand)]
+    Webhook(WebhookCommand),
+    /// Searches the transactions of the account
+    #[command(subcommand)]
+    Transactions(TransactionsCommand),
+}
+
+#[derive(Subcommand)]
+enum PaymentCommand {
+    /// Shows a payment
+    Get {
+        /// The ID of the payment
+        id: String,
+    },
+    /// Lists the most recent payments
+    List,
+    /// Executes a payment the payer approved
+    Execute {
+        /// The ID of the payment
+        id: String,
+        /// The payer ID PayPal appended to the return URL
+        payer_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum OrderCommand {
+    /// Creates an order, printing the URL the payer approves it at
+    Create {
+        /// The amount to charge, such as 10.00
+        #[arg(long)]
+        amount: String,
+        /// The three-letter currency code of the amount
+        #[arg(long)]
+        currency: String,
+        /// CAPTURE or AUTHORIZE
+        #[arg(
+            long,
+            default_value = "CAPTURE",
+            value_parser = ["CAPTURE", "AUTHORIZE"],
+            ignore_case = true
+        )]
+        intent: String,
+    },
+    /// Shows an order
+    Get {
+        /// The ID of the order
+        id: String,
+    },
+    /// Captures the payment of an order the payer approved
+    Capture {
+        /// The ID of the order
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum RefundCommand {
+    /// Refunds a payment captured for a v2 order
+    Capture {
+        /// The ID of the capture
+        id: String,
+        #[command(flatten)]
+        amount: RefundAmount,
+    },
+    /// Refunds a v1 sale
+    Sale {
+        /// The ID of the sale
+        id: String,
+        #[command(flatten)]
+        amount: RefundAmount,
+    },
+}
+
+#[derive(clap::Args)]
+struct RefundAmount {
+    /// The amount to refund, the whole payment if not given
+    #[arg(long, requires = "currency")]
+    amount: Option<String>,
+    /// The three-letter currency code of the amount
+    #[arg(long, requires = "amount")]
+    currency: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum WebhookCommand {
+    /// Asks PayPal whether an event was really sent by PayPal, exiting with 1 if not
+    Verify {
+        /// The ID of the webhook the event was delivered to
+        #[arg(long)]
+        webhook_id: String,
+        /// The PAYPAL-AUTH-ALGO header of the delivery
+        #[arg(long)]
+        auth_algo: String,
+        /// The PAYPAL-CERT-URL header of the delivery
+        #[arg(long)]
+        cert_url: String,
+        /// The PAYPAL-TRANSMISSION-ID header of the delivery
+        #[arg(long)]
+        transmission_id: String,
+        /// The PAYPAL-TRANSMISSION-SIG header of the delivery
+        #[arg(long)]
+        transmission_sig: String,
+        /// The PAYPAL-TRANSMISSION-TIME header of the delivery
+        #[arg(long)]
+        transmission_time: String,
+        /// The file holding the event body as delivered, or - for standard input
+        event: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum TransactionsCommand {
+    /// Lists the transactions between two times
+    Search {
+        /// The start of the search, as an RFC 3339 time such as 2024-01-01T00:00:00Z
+        #[arg(long)]
+        start: DateTime<Utc>,
+        /// The end of the search, at most 31 days after the start
+        #[arg(long)]
+        end: DateTime<Utc>,
+        /// Only the transaction with this ID
+        #[arg(long)]
+        id: Option<String>,
+        /// Stop after this many transactions
+        #[arg(long)]
+        max: Option<usize>,
+    },
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = run(cli) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let credentials = config::credentials(cli.config.as_deref())?;
+    let base_url = match (cli.sandbox, &cli.base_url) {
+        (true, _) => SANDBOX_URL,
+        (false, Some(base_url)) => base_url.as_str(),
+        (false, None) => LIVE_URL,
+    };
+    let token = paypal::get_token_from(
+        base_url,
+        credentials.client_id(),
+        credentials.expose_secret(),
+    )?;
+    let client = Client::new(&token.access_token).with_base_url(base_url);
+    match cli.command {
+        Command::Token => match cli.output {
+            Format::Json => output::json(&token),
+            // bare, so that it can be used as `curl -H "Authorization: Bearer $(paypal token)"`
+            Format::Table => {
+                println!("{}", token.access_token.expose_secret());
+                Ok(())
+            }
+        },
+        Command::Payment(PaymentCommand::Get { id }) => {
+            print_payments(cli.output, &[payment::get(&client, &id)?])
+        }
+        Command::Payment(PaymentCommand::List) => {
+            print_payments(cli.output, &payment::list(&client)?.payments)
+        }
+        Command::Payment(PaymentCommand::Execute { id, payer_id }) => {
+            print_payments(cli.output, &[payment::execute(&client, &id, &payer_id)?])
+        }
+        Command::Order(OrderCommand::Create {
+            amount,
+            currency,
+            intent,
+        }) => {
+            let request = OrderRequest {
+                intent: OrderIntent::from(intent.to_uppercase().as_str()),
+                purchase_units: vec![PurchaseUnit {
+                    amount: money(amount, currency),
+                    ..Default::default()
+                }],
+                application_context: None,
+            };
+            print_order(cli.output, &order::create(&client, &request)?)
+        }
+        Command::Order(OrderCommand::Get { id }) => {
+            print_order(cli.output, &order::get(&client, &id)?)
+        }
+        Command::Order(OrderCommand::Capture { id }) => {
+            print_order(cli.output, &order::capture(&client, &id)?)
+        }
+        Command::Refund(RefundCommand::Capture { id, amount }) => {
+            let amount = amount.into_money();
+            let refund = capture::refund(&client, &id, amount.as_ref())?;
+            if cli.output == Format::Json {
+                return output::json(&refund);
+            }
+            print_refund(
+                &refund.id,
+                refund.status.as_ref().map(ToString::to_string),
+                refund.amount.as_ref().map(format_money),
+            );
+            Ok(())
+        }
+        Command::Refund(RefundCommand::Sale { id, amount }) => {
+            let amount = amount.into_money().map(|money| TransactionAmount {
+                currency: money.currency_code,
+                total: money.value,
+                ..Default::default()
+            });
+            let refund = sale::refund(&client, &id, amount.as_ref())?;
+            if cli.output == Format::Json {
+                return output::json(&refund);
+            }
+            print_refund(
+                &refund.id,
+                refund.state.as_ref().map(ToString::to_string),
+                refund
+                    .amount
+                    .as_ref()
+                    .map(|amount| format!("{} {}", amount.total, amount.currency)),
+            );
+            Ok(())
+        }
+        Command::Webhook(WebhookCommand::Verify {
+            webhook_id,
+            auth_algo,
+            cert_url,
+            transmission_id,
+            transmission_sig,
+            transmission_time,
+            event,
+        }) => {
+            let signature = WebhookSignature {
+                auth_algo,
+                cert_url,
+                transmission_id,
+                transmission_sig,
+                transmission_time,
+                webhook_id,
+                webhook_event: serde_json::from_str(&read_event(&event)?)?,
+            };
+            let status = webhook::verify(&client, &signature)?;
+            match cli.output {
+                Format::Json => output::json(&status)?,
+                Format::Table => println!("{}", status),
+            }
+            if status != VerificationStatus::Success {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Transactions(TransactionsCommand::Search {
+            start,
+            end,
+            id,
+            max,
+        }) => {
+            let mut search = TransactionSearch::new(start, end);
+            search.transaction_id = id;
+            let mut found = transaction::search(&client, &search).with_page_size(100);
+            if let Some(max) = max {
+                found = found.with_max_items(max);
+            }
+            let found = found.collect::<Result<Vec<_>, _>>()?;
+            if cli.output == Format::Json {
+                return output::json(&found);
+            }
+            let mut table = Table::new(&["ID", "STATUS", "EVENT", "AMOUNT", "FEE", "DATE"]);
+            for details in &found {
+                let info = &details.transaction_info;
+                table.row(vec![
+                    info.transaction_id.clone(),
+                    display(&info.transaction_status),
+                    info.transaction_event_code.clone().unwrap_or_default(),
+                    info.transaction_amount
+                        .as_ref()
+                        .map(format_money)
+                        .unwrap_or_default(),
+                    info.fee_amount
+                        .as_ref()
+                        .map(format_money)
+                        .unwrap_or_default(),
+                    info.transaction_initiation_date
+                        .map(|time| time.to_rfc3339())
+                        .unwrap_or_default(),
+                ]);
+            }
+            print!("{}", table);
+            Ok(())
+        }
+    }
+}
+
+impl RefundAmount {
+    fn into_money(self) -> Option<Money> {
+        Some(money(self.amount?, self.currency?))
+    }
+}
+
+fn money(value: String, currency_code: String) -> Money {
+    Money {
+        currency_code: currency_code.to_uppercase(),
+        value,
+        ..Default::default()
+    }
+}
+
+fn format_money(money: &Money) -> String {
+    format!("{} {}", money.value, money.currency_code)
+}
+
+fn display<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+fn read_event(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    if path.as_os_str() == "-" {
+        let mut event = String::new();
+        std::io::stdin().read_to_string(&mut event)?;
+        return Ok(event);
+    }
+    std::fs::read_to_string(path)
+        .map_err(|err| format!("cannot read {}: {}", path.display(), err).into())
+}
+
+fn print_order(format: Format, order: &Order) -> Result<(), Box<dyn std::error::Error>> {
+    if format == Format::Json {
+        return output::json(order);
+    }
+    let mut table = Table::new(&["ID", "STATUS", "AMOUNT", "CAPTURES", "APPROVE AT"]);
+    let captures = order
+        .purchase_units
+        .iter()
+        .flat_map(|unit| unit.payments.iter().flat_map(|payments| &payments.captures))
+        .map(|capture| capture.id.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+    table.row(vec![
+        order.id.clone(),
+        display(&order.status),
+        order
+            .purchase_units
+            .first()
+            .map(|unit| format_money(&unit.amount))
+            .unwrap_or_default(),
+        captures,
+        order.approval_url().unwrap_or_default().to_string(),
+    ]);
+    print!("{}", table);
+    Ok(())
+}
+
+fn print_refund(id: &str, status: Option<String>, amount: Option<String>) {
+    let mut table = Table::new(&["ID", "STATUS", "AMOUNT"]);
+    table.row(vec![
+        id.to_string(),
+        status.unwrap_or_default(),
+        amount.unwrap_or_default(),
+    ]);
+    print!("{}", table);
+}
+
+fn print_payments(format: Format, payments: &[Payment]) -> Result<(), Box<dyn std::error::Error>> {
+    if format == Format::Json {
+        return output::json(&payments);
+    }
+    let mut table = Table::new(&["ID", "STATE", "INTENT", "TOTAL", "CREATED"]);
+    for payment in payments {
+        let total = payment
+            .transactions
+            .first()
+            .map(|transaction| {
+                format!(
+                    "{} {}",
+                    transaction.amount.total, transaction.amount.currency
+                )
+            })
+            .unwrap_or_default();
+        table.row(vec![
+            payment.id.clone(),
+            payment
+                .state
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            payment.intent.to_string(),
+            total,
+            payment
+                .create_time
+                .map(|time| time.to_rfc3339())
+                .unwrap_or_default(),
+        ]);
+    }
+    print!("{}", table);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sandbox_conflicts_with_base_url() {
+        let parse = |args: &[&str]| Cli::try_parse_from(args.iter().copied());
+        assert!(parse(&["paypal", "--sandbox", "token"]).is_ok());
+        assert!(parse(&["paypal", "--base-url", "http://localhost:8080", "token"]).is_ok());
+        assert!(parse(&[
+            "paypal",
+            "--sandbox",
+            "--base-url",
+            "http://localhost:8080",
+            "token"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_order_intent() {
+        let intent = |intent: &str| -> Result<String, clap::Error> {
+            let args = [
+                "paypal",
+                "order",
+                "create",
+                "--amount",
+                "10.00",
+                "--currency",
+                "USD",
+                "--intent",
+                intent,
+            ];
+            match Cli::try_parse_from(args.iter().copied())?.command {
+                Command::Order(OrderCommand::Create { intent, .. }) => Ok(intent),
+                _ => unreachable!(),
+            }
+        };
+        assert_eq!("AUTHORIZE", intent("AUTHORIZE").unwrap());
+        assert!(intent("authorize").is_ok());
+        assert!(intent("CAPTUER").is_err());
+    }
+}
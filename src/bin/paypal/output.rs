@@ -0,0 +1,69 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// PayPal's response, as pretty-printed JSON
+    Json,
+    /// The most useful fields, as aligned columns
+    Table,
+}
+
+pub fn json<T: serde::Serialize>(value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+// columns separated by two spaces, each as wide as its widest cell
+pub struct Table {
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            rows: vec![headers.iter().map(ToString::to_string).collect()],
+        }
+    }
+
+    pub fn row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut widths = Vec::new();
+        for row in &self.rows {
+            widths.resize(widths.len().max(row.len()), 0);
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        for row in &self.rows {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::new(&["ID", "STATE"]);
+        table.row(vec!["PAYID-LZ7ZYRA".into(), "created".into()]);
+        table.row(vec!["PAY-1".into(), "approved".into()]);
+        assert_eq!(
+            "ID             STATE\nPAYID-LZ7ZYRA  created\nPAY-1          approved\n",
+            table.to_string()
+        );
+    }
+}
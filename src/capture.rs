@@ -0,0 +1,77 @@
+use crate::client::Client;
+use crate::request::Resp;
+use crate::types::{Capture, CaptureRefund, Money, RequestRefund};
+
+/// Shows the details of a payment captured for a v2 order, by ID.
+pub fn get(client: &Client, capture_id: &str) -> Resp<Capture> {
+    crate::request::get(
+        client,
+        &client.endpoint(&format!("/v2/payments/captures/{}", capture_id)),
+    )
+}
+
+/// Refunds a payment captured for a v2 order, either in full or, when `amount` is given, in
+/// part.
+///
+/// ```rust,no_run
+/// use paypal::{capture, get_token, Client, Money};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let amount = Money {
+///     currency_code: "USD".to_string(),
+///     value: "10.00".to_string(),
+///     ..Default::default()
+/// };
+/// let client = Client::new(&token.access_token);
+/// let refund = capture::refund(&client, "2GG279541U471931P", Some(&amount)).unwrap();
+/// ```
+pub fn refund(client: &Client, capture_id: &str, amount: Option<&Money>) -> Resp<CaptureRefund> {
+    crate::request::post_json(
+        client,
+        &client.endpoint(&format!("/v2/payments/captures/{}/refund", capture_id)),
+        &RequestRefund { amount },
+    )
+}
+
+/// Shows the details of a refund of a v2 capture, by ID.
+pub fn get_refund(client: &Client, refund_id: &str) -> Resp<CaptureRefund> {
+    crate::request::get(
+        client,
+        &client.endpoint(&format!("/v2/payments/refunds/{}", refund_id)),
+    )
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::types::{CaptureStatus, OrderIntent, OrderRequest, PurchaseUnit, RefundStatus};
+
+    #[test]
+    fn test_refund() {
+        let server = crate::testing::MockServer::start();
+        let client = server.client();
+        let order = OrderRequest {
+            intent: OrderIntent::Capture,
+            purchase_units: vec![PurchaseUnit {
+                amount: Money {
+                    currency_code: "USD".to_string(),
+                    value: "100.00".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            application_context: None,
+        };
+        let order = crate::order::create(&client, &order).unwrap();
+        server.approve_order(&order.id, "PAYER42").unwrap();
+        let order = crate::order::capture(&client, &order.id).unwrap();
+        let captured = &order.purchase_units[0].payments.as_ref().unwrap().captures[0];
+
+        let refund = refund(&client, &captured.id, None).unwrap();
+        assert_eq!(Some(RefundStatus::Completed), refund.status);
+        assert_eq!("100.00", refund.amount.unwrap().value);
+        assert_eq!(refund.id, get_refund(&client, &refund.id).unwrap().id);
+        let captured = get(&client, &captured.id).unwrap();
+        assert_eq!(Some(CaptureStatus::Refunded), captured.status);
+    }
+}
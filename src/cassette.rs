@@ -0,0 +1,429 @@
+use crate::error::Error;
+use crate::redact;
+use crate::request::Resp;
+use crate::transport::{HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Tape>> = const { RefCell::new(None) };
+}
+
+/// Whether a cassette records real exchanges or replays recorded ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Sends every request to PayPal and records the exchange into the cassette file.
+    Record,
+    /// Answers every request from the cassette file, without touching the network.
+    Replay,
+}
+
+/// Records the HTTP exchanges made by this crate into a JSON file, and replays them later so
+/// that tests exercising real PayPal responses run deterministically and offline.
+///
+/// `Authorization` and `Set-Cookie` headers, access tokens, card numbers and CVVs are scrubbed
+/// from requests and responses before anything is written. Requests are replayed in the order
+/// they were recorded and must match the recorded method, path and body. Bodies are compared as
+/// JSON once scrubbed, so the order of their fields does not matter.
+///
+/// ```rust,no_run
+/// use paypal::cassette::Cassette;
+///
+/// let token = Cassette::new("tests/fixtures/get_token.json")
+///     .run(|| paypal::get_token("my_id", "my_secret"))
+///     .unwrap();
+/// ```
+pub struct Cassette {
+    path: PathBuf,
+    mode: Mode,
+    match_bodies: bool,
+}
+
+impl Cassette {
+    /// A cassette stored at `path`. It replays, unless the `PAYPAL_CASSETTE` environment variable
+    /// is set to `record`.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        let mode = match std::env::var("PAYPAL_CASSETTE") {
+            Ok(ref mode) if mode == "record" => Mode::Record,
+            _ => Mode::Replay,
+        };
+        Self::with_mode(path, mode)
+    }
+
+    /// A cassette stored at `path`, using the given mode regardless of the environment.
+    pub fn with_mode<P: Into<PathBuf>>(path: P, mode: Mode) -> Self {
+        Self {
+            path: path.into(),
+            mode,
+            match_bodies: true,
+        }
+    }
+
+    /// Replays requests whatever their body, for requests whose body changes from run to run,
+    /// such as one holding a timestamp.
+    pub fn ignore_bodies(mut self) -> Self {
+        self.match_bodies = false;
+        self
+    }
+
+    /// Runs `f` with the cassette inserted: every request `f` makes on the current thread is
+    /// recorded or replayed. When recording, the cassette file is written once `f` returns.
+    ///
+    /// # Panics
+    ///
+    /// When replaying and the cassette file is missing or malformed.
+    pub fn run<T, F: FnOnce() -> T>(self, f: F) -> T {
+        let interactions = match self.mode {
+            Mode::Record => Vec::new(),
+            Mode::Replay => self.load(),
+        };
+        CURRENT.with(|current| {
+            *current.borrow_mut() = Some(Tape {
+                mode: self.mode,
+                interactions,
+                match_bodies: self.match_bodies,
+                position: 0,
+                performing: false,
+            })
+        });
+        let _eject = Eject(self.mode, self.path);
+        f()
+    }
+
+    fn load(&self) -> Vec<Interaction> {
+        let file = std::fs::read_to_string(&self.path).unwrap_or_else(|err| {
+            panic!(
+                "cassette {} could not be read ({}), record it with PAYPAL_CASSETTE=record",
+                self.path.display(),
+                err
+            )
+        });
+        let recording: Recording = serde_json::from_str(&file)
+            .unwrap_or_else(|err| panic!("cassette {} is malformed: {}", self.path.display(), err));
+        recording.interactions
+    }
+}
+
+// removes the cassette from the thread once `run` is done, and saves what was recorded
+struct Eject(Mode, PathBuf);
+
+impl Drop for Eject {
+    fn drop(&mut self) {
+        let tape = CURRENT.with(|current| current.borrow_mut().take());
+        if self.0 != Mode::Record || std::thread::panicking() {
+            return;
+        }
+        if let Some(tape) = tape {
+            let recording = Recording {
+                interactions: tape.interactions,
+            };
+            if let Some(dir) = self.1.parent() {
+                std::fs::create_dir_all(dir).expect("a writable cassette directory");
+            }
+            let json = serde_json::to_string_pretty(&recording).expect("a serializable recording");
+            std::fs::write(&self.1, json + "\n").expect("a writable cassette file");
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Recording {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    path: String,
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    body: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    body: Value,
+}
+
+struct Tape {
+    mode: Mode,
+    interactions: Vec<Interaction>,
+    match_bodies: bool,
+    position: usize,
+    // whether a recorded request is being performed
+    performing: bool,
+}
+
+impl Tape {
+    fn replay(&mut self, request: &HttpRequest) -> Resp<HttpResponse> {
+        let path = path_of(&request.url);
+        let interaction = self.interactions.get(self.position).ok_or_else(|| {
+            Error::new(format!(
+                "cassette has no recorded response left for {} {}",
+                request.method, path
+            ))
+        })?;
+        if interaction.request.method != request.method.as_str() || interaction.request.path != path
+        {
+            return Err(Error::new(format!(
+                "cassette expected {} {} but got {} {}",
+                interaction.request.method, interaction.request.path, request.method, path
+            )));
+        }
+        if self.match_bodies {
+            let body = redact::body(request.body.as_deref().unwrap_or_default());
+            if interaction.request.body != body {
+                return Err(Error::new(format!(
+                    "cassette expected {} {} with body {} but got {}",
+                    interaction.request.method, path, interaction.request.body, body
+                )));
+            }
+        }
+        self.position += 1;
+        Ok(HttpResponse {
+            status: interaction.response.status,
+            headers: interaction.response.headers.clone().into_iter().collect(),
+            body: body_to_string(&interaction.response.body).into_bytes(),
+        })
+    }
+
+    fn record(&mut self, request: &HttpRequest, response: &HttpResponse) {
+        self.interactions.push(Interaction {
+            request: RecordedRequest {
+                method: request.method.to_string(),
+                path: path_of(&request.url),
+                headers: redact::headers(&request.headers),
+                body: redact::body(request.body.as_deref().unwrap_or_default()),
+            },
+            response: RecordedResponse {
+                status: response.status,
+                headers: redact::headers(&response.headers),
+                body: redact::body(&response.body),
+            },
+        });
+    }
+}
+
+// Called for every request. Returns `None` when no cassette is inserted on this thread.
+//
+// The tape is not borrowed while the request is performed, because a transport may itself make
+// requests. Those are performed but not recorded: replaying the outer request does not call the
+// transport, so they would never be asked for again.
+pub(crate) fn intercept<F>(request: &HttpRequest, perform: F) -> Option<Resp<HttpResponse>>
+where
+    F: FnOnce() -> Resp<HttpResponse>,
+{
+    // whether this request is nested in a recorded one, or `None` when replaying
+    let nested = CURRENT.with(|current| {
+        let mut current = current.borrow_mut();
+        let tape = current.as_mut()?;
+        Some(match tape.mode {
+            Mode::Record => Some(std::mem::replace(&mut tape.performing, true)),
+            Mode::Replay => None,
+        })
+    })?;
+    let nested = match nested {
+        Some(nested) => nested,
+        None => {
+            return CURRENT.with(|current| {
+                let mut current = current.borrow_mut();
+                current.as_mut().map(|tape| tape.replay(request))
+            })
+        }
+    };
+    let response = perform();
+    if !nested {
+        CURRENT.with(|current| {
+            if let Some(tape) = current.borrow_mut().as_mut() {
+                tape.performing = false;
+                if let Ok(response) = &response {
+                    tape.record(request, response);
+                }
+            }
+        });
+    }
+    Some(response)
+}
+
+// recordings only keep the path, so that they replay against any base url
+fn path_of(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(url) => match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().into(),
+        },
+        Err(_) => url.into(),
+    }
+}
+
+fn body_to_string(body: &Value) -> String {
+    match body {
+        Value::Null => String::new(),
+        Value::String(body) => body.clone(),
+        json => json.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_replay_checks_order() {
+        let mut tape = Tape {
+            mode: Mode::Replay,
+            interactions: vec![Interaction {
+                request: RecordedRequest {
+                    method: "GET".into(),
+                    path: "/v1/payments/payment".into(),
+                    headers: BTreeMap::new(),
+                    body: Value::Null,
+                },
+                response: RecordedResponse {
+                    status: 200,
+                    headers: BTreeMap::new(),
+                    body: json!({ "payments": [], "count": 0 }),
+                },
+            }],
+            match_bodies: true,
+            position: 0,
+            performing: false,
+        };
+        let mut request = HttpRequest {
+            method: reqwest::Method::POST,
+            url: "https://api.sandbox.paypal.com/v1/payments/payment".into(),
+            headers: Default::default(),
+            body: None,
+            timeout: None,
+        };
+        assert!(tape.replay(&request).is_err());
+        request.method = reqwest::Method::GET;
+        let response = tape.replay(&request).unwrap();
+        assert_eq!(&br#"{"count":0,"payments":[]}"#[..], &response.body[..]);
+        assert!(tape.replay(&request).is_err());
+    }
+
+    #[test]
+    fn test_replay_checks_body() {
+        let interaction = || Interaction {
+            request: RecordedRequest {
+                method: "POST".into(),
+                path: "/v1/payments/sale/ABC/refund".into(),
+                headers: BTreeMap::new(),
+                body: json!({ "amount": { "total": "1.00", "currency": "USD" } }),
+            },
+            response: RecordedResponse {
+                status: 201,
+                headers: BTreeMap::new(),
+                body: json!({ "id": "REFUND" }),
+            },
+        };
+        let mut tape = Tape {
+            mode: Mode::Replay,
+            interactions: vec![interaction(), interaction()],
+            match_bodies: true,
+            position: 0,
+            performing: false,
+        };
+        let mut request = HttpRequest {
+            method: reqwest::Method::POST,
+            url: "https://api.sandbox.paypal.com/v1/payments/sale/ABC/refund".into(),
+            headers: Default::default(),
+            body: Some(br#"{"amount":{"total":"2.00","currency":"USD"}}"#.to_vec()),
+            timeout: None,
+        };
+        assert!(tape.replay(&request).is_err());
+        // fields in another order, with other whitespace
+        request.body = Some(br#"{ "amount": { "currency": "USD", "total": "1.00" } }"#.to_vec());
+        assert!(tape.replay(&request).is_ok());
+
+        tape.match_bodies = false;
+        request.body = None;
+        assert!(tape.replay(&request).is_ok());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_record_then_replay() {
+        let path =
+            std::env::temp_dir().join(format!("paypal-cassette-{}.json", std::process::id()));
+        let server = crate::testing::MockServer::start();
+        let url = server.url();
+        let recorded = Cassette::with_mode(&path, Mode::Record)
+            .run(|| crate::get_token_from(&url, crate::testing::CLIENT_ID, crate::testing::SECRET))
+            .unwrap();
+        drop(server);
+        let file = std::fs::read_to_string(&path).unwrap();
+        assert!(!file.contains(recorded.access_token.expose_secret()));
+
+        let replayed = Cassette::with_mode(&path, Mode::Replay)
+            .run(|| crate::get_token_from(&url, "", ""))
+            .unwrap();
+        assert_eq!(redact::REDACTED, replayed.access_token.expose_secret());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_nested_requests_are_not_recorded() {
+        use crate::client::Client;
+        use crate::transport::Transport;
+
+        // answers every request with an empty body
+        struct Empty;
+
+        impl Transport for Empty {
+            fn send(&self, _: &HttpRequest) -> Resp<HttpResponse> {
+                Ok(HttpResponse {
+                    status: 204,
+                    headers: [("set-cookie".to_string(), "session=secret".to_string())]
+                        .iter()
+                        .cloned()
+                        .collect(),
+                    body: Vec::new(),
+                })
+            }
+        }
+
+        // makes a request of its own before answering, like a transport fetching a token
+        struct Nesting;
+
+        impl Transport for Nesting {
+            fn send(&self, request: &HttpRequest) -> Resp<HttpResponse> {
+                let inner = Client::new("token")
+                    .with_base_url("https://example.com")
+                    .with_transport(Empty);
+                crate::web_profile::delete(&inner, "XP-INNER")?;
+                Empty.send(request)
+            }
+        }
+
+        let path = std::env::temp_dir().join(format!("paypal-nested-{}.json", std::process::id()));
+        let client = Client::new("token")
+            .with_base_url("https://example.com")
+            .with_transport(Nesting);
+        Cassette::with_mode(&path, Mode::Record)
+            .run(|| crate::web_profile::delete(&client, "XP-OUTER"))
+            .unwrap();
+        let recording: Recording =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(1, recording.interactions.len());
+        let interaction = &recording.interactions[0];
+        assert!(interaction.request.path.ends_with("/XP-OUTER"));
+        assert_eq!(redact::REDACTED, interaction.response.headers["set-cookie"]);
+    }
+}
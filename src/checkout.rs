@@ -0,0 +1,405 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::Resp;
+use crate::types::{Order, OrderApplicationContext, OrderIntent, OrderRequest, PurchaseUnit};
+use crate::types::{Payment, PaymentIntent, PaymentMethod, Transaction};
+use base64::{decode_config, encode_config, URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A checkout waiting for the payer, returned by `start` for a payment and by `start_order` for a
+/// v2 order.
+#[derive(Debug)]
+pub struct Started<T = Payment> {
+    /// The payment or order as PayPal created it.
+    pub created: T,
+    /// Where to redirect the payer, so that they approve the payment.
+    pub approval_url: String,
+    /// What `finish` or `finish_order` needs to know about this checkout. Keep it in the payer's
+    /// session until they come back. It is neither encrypted nor signed, so it does not belong in
+    /// a cookie.
+    pub state: String,
+}
+
+/// How a checkout ended, as returned by `finish` and `finish_order`.
+#[derive(Debug)]
+pub enum Outcome<T = Payment> {
+    /// The payer approved the payment, and it was executed, or the order captured or authorized.
+    Completed(T),
+    /// The payer cancelled on the PayPal pages and came back through the cancel URL.
+    Cancelled,
+    /// The payment had already been executed, or the order captured or authorized, because the
+    /// payer's browser loaded the return URL again. This is the payment or order as it stands.
+    Replayed(T),
+}
+
+// what `Started::state` encodes
+#[derive(Serialize, Deserialize)]
+struct State {
+    // the ID of the payment, or of the order
+    id: String,
+    // the `token` query parameter PayPal sends the payer back with
+    token: Option<String>,
+    // set for orders only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    order_intent: Option<OrderIntent>,
+}
+
+/// Creates a PayPal payment and returns where to send the payer to approve it. Once they come
+/// back, pass the query string of the URL they came back to to `finish`.
+///
+/// ```rust,no_run
+/// use paypal::{checkout, Client, PaymentIntent, Transaction, TransactionAmount};
+///
+/// let client = Client::new("token");
+/// let amount = TransactionAmount {
+///     currency: "USD".to_string(),
+///     total: "100.00".to_string(),
+///     ..Default::default()
+/// };
+/// let started = checkout::start(
+///     &client,
+///     "https://mysite.com/paypal/return",
+///     "https://mysite.com/paypal/cancel",
+///     PaymentIntent::Sale,
+///     vec![Transaction { amount, ..Default::default() }],
+/// )
+/// .unwrap();
+/// // store `started.state` in the session, then redirect to `started.approval_url`
+/// ```
+pub fn start(
+    client: &Client,
+    return_url: &str,
+    cancel_url: &str,
+    intent: PaymentIntent,
+    transactions: Vec<Transaction>,
+) -> Resp<Started> {
+    let payment = crate::payment::new(
+        client,
+        return_url,
+        cancel_url,
+        PaymentMethod::Paypal,
+        intent,
+        transactions,
+    )?;
+    let approval_url = payment
+        .approval_url()
+        .ok_or_else(|| Error::new(format!("payment {} has no approval URL", payment.id)))?
+        .to_string();
+    let state = State {
+        id: payment.id.clone(),
+        token: query(&approval_url).remove("token"),
+        order_intent: None,
+    };
+    Ok(Started {
+        state: encode(&state)?,
+        created: payment,
+        approval_url,
+    })
+}
+
+/// Like `start`, but creates a v2 order, which `finish_order` captures or authorizes once the
+/// payer approved it, as its `intent` says.
+///
+/// ```rust,no_run
+/// use paypal::{checkout, Client, Money, OrderIntent, PurchaseUnit};
+///
+/// let client = Client::new("token");
+/// let amount = Money {
+///     currency_code: "USD".to_string(),
+///     value: "100.00".to_string(),
+///     ..Default::default()
+/// };
+/// let started = checkout::start_order(
+///     &client,
+///     "https://mysite.com/paypal/return",
+///     "https://mysite.com/paypal/cancel",
+///     OrderIntent::Capture,
+///     vec![PurchaseUnit { amount, ..Default::default() }],
+/// )
+/// .unwrap();
+/// // store `started.state` in the session, then redirect to `started.approval_url`
+/// ```
+pub fn start_order(
+    client: &Client,
+    return_url: &str,
+    cancel_url: &str,
+    intent: OrderIntent,
+    purchase_units: Vec<PurchaseUnit>,
+) -> Resp<Started<Order>> {
+    let request = OrderRequest {
+        intent: intent.clone(),
+        purchase_units,
+        application_context: Some(OrderApplicationContext {
+            return_url: Some(return_url.into()),
+            cancel_url: Some(cancel_url.into()),
+            ..Default::default()
+        }),
+    };
+    let order = crate::order::create(client, &request)?;
+    let approval_url = order
+        .approval_url()
+        .ok_or_else(|| Error::new(format!("order {} has no approval URL", order.id)))?
+        .to_string();
+    // PayPal sends the payer back with the order ID as the token
+    let state = State {
+        id: order.id.clone(),
+        token: Some(order.id.clone()),
+        order_intent: Some(intent),
+    };
+    Ok(Started {
+        state: encode(&state)?,
+        created: order,
+        approval_url,
+    })
+}
+
+/// Finishes the checkout whose `state` `start` returned, from the query string, or the whole
+/// URL, the payer came back to. Executes the payment if they approved it.
+///
+/// Fails when the URL belongs to another checkout, for example because the payer tampered with
+/// it.
+///
+/// ```rust,no_run
+/// use paypal::checkout::{self, Outcome};
+/// use paypal::Client;
+///
+/// let client = Client::new("token");
+/// # let state = "";
+/// let query = "paymentId=PAYID-LWZ7Q6A3SE48217XB7361725&token=EC-2JT80165HL3458339&PayerID=QQGSRNHDACTLJ";
+/// match checkout::finish(&client, state, query).unwrap() {
+///     Outcome::Completed(payment) => println!("paid: {}", payment.id),
+///     Outcome::Cancelled => println!("cancelled"),
+///     Outcome::Replayed(payment) => println!("already paid: {}", payment.id),
+/// }
+/// ```
+pub fn finish(client: &Client, state: &str, return_query: &str) -> Resp<Outcome> {
+    let (state, params) = decode(state, return_query, false)?;
+    let payer_id = match params.get("PayerID") {
+        Some(payer_id) => payer_id,
+        None => return Ok(Outcome::Cancelled),
+    };
+    match crate::payment::execute(client, &state.id, payer_id) {
+        Ok(payment) => Ok(Outcome::Completed(payment)),
+        Err(err) if already_done(&err) => {
+            Ok(Outcome::Replayed(crate::payment::get(client, &state.id)?))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Like `finish`, for the checkout of a v2 order whose `state` `start_order` returned. Captures
+/// or authorizes the order if the payer approved it.
+pub fn finish_order(client: &Client, state: &str, return_query: &str) -> Resp<Outcome<Order>> {
+    let (state, params) = decode(state, return_query, true)?;
+    if !params.contains_key("PayerID") {
+        return Ok(Outcome::Cancelled);
+    }
+    let completed = match state.order_intent {
+        Some(OrderIntent::Authorize) => crate::order::authorize(client, &state.id),
+        _ => crate::order::capture(client, &state.id),
+    };
+    match completed {
+        Ok(order) => Ok(Outcome::Completed(order)),
+        Err(err) if already_done(&err) => {
+            Ok(Outcome::Replayed(crate::order::get(client, &state.id)?))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn encode(state: &State) -> Resp<String> {
+    Ok(encode_config(&serde_json::to_vec(state)?, URL_SAFE_NO_PAD))
+}
+
+// the state of a payment checkout, or an order one when `order`, once the return query is checked
+// to belong to it. PayPal sends the payer back to the cancel URL with the token only, so a query
+// without a `PayerID` must carry the token to count as a cancellation.
+fn decode(state: &str, return_query: &str, order: bool) -> Resp<(State, HashMap<String, String>)> {
+    let state: State = decode_config(state, URL_SAFE_NO_PAD)
+        .ok()
+        .and_then(|state| serde_json::from_slice(&state).ok())
+        .filter(|state: &State| state.order_intent.is_some() == order)
+        .ok_or_else(|| Error::new("invalid checkout state".into()))?;
+    let params = query(return_query);
+    let token_matches = match (params.get("token"), &state.token) {
+        (Some(token), Some(expected)) => Some(token == expected),
+        _ => None,
+    };
+    // an order's return URL carries nothing but the token to tie it to the order
+    let belongs = params.get("paymentId").map_or(true, |id| *id == state.id)
+        && if order || !params.contains_key("PayerID") {
+            token_matches == Some(true)
+        } else {
+            token_matches != Some(false)
+        };
+    if !belongs {
+        return Err(Error::new(format!(
+            "the return URL does not belong to {} {}",
+            if order { "order" } else { "payment" },
+            state.id
+        )));
+    }
+    Ok((state, params))
+}
+
+// the parameters of a query string, or of the query of a URL
+fn query(url: &str) -> HashMap<String, String> {
+    let query = url.split_once('?').map_or(url, |(_, query)| query);
+    serde_urlencoded::from_str(query).unwrap_or_default()
+}
+
+// whether PayPal refused to execute a payment, or to capture or authorize an order, because that
+// was done already
+fn already_done(err: &Error) -> bool {
+    let body: serde_json::Value = match serde_json::from_str(err.message()) {
+        Ok(body) => body,
+        Err(_) => return false,
+    };
+    let issues = body["details"].as_array().into_iter().flatten();
+    body["name"] == "PAYMENT_ALREADY_DONE"
+        || issues
+            .map(|detail| &detail["issue"])
+            .any(|issue| issue == "ORDER_ALREADY_CAPTURED" || issue == "ORDER_ALREADY_AUTHORIZED")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpRequest, HttpResponse, Transport};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    const PAYMENT_ID: &str = "PAYID-LWZ7Q6A3SE48217XB7361725";
+
+    // creates the payment, and executes it once
+    #[derive(Default)]
+    struct Paypal {
+        executed: AtomicBool,
+    }
+
+    impl Transport for Paypal {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+            let payment = |state: &str| {
+                serde_json::json!({
+                    "id": PAYMENT_ID,
+                    "intent": "sale",
+                    "state": state,
+                    "payer": { "payment_method": "paypal" },
+                    "transactions": [{ "amount": { "total": "100.00", "currency": "USD" } }],
+                    "links": [{
+                        "href": "https://www.sandbox.paypal.com/cgi-bin/webscr?cmd=_express-checkout&token=EC-2JT80165HL3458339",
+                        "rel": "approval_url",
+                        "method": "REDIRECT"
+                    }]
+                })
+            };
+            let (status, body) = if request.url.ends_with("/execute") {
+                if self.executed.swap(true, Ordering::SeqCst) {
+                    (400, serde_json::json!({ "name": "PAYMENT_ALREADY_DONE" }))
+                } else {
+                    (200, payment("approved"))
+                }
+            } else if request.method == reqwest::Method::POST {
+                (201, payment("created"))
+            } else {
+                (200, payment("approved"))
+            };
+            Ok(HttpResponse {
+                status,
+                headers: Default::default(),
+                body: body.to_string().into_bytes(),
+            })
+        }
+    }
+
+    fn start(client: &Client) -> Started {
+        super::start(
+            client,
+            "https://mysite.com/return",
+            "https://mysite.com/cancel",
+            PaymentIntent::Sale,
+            Vec::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_checkout() {
+        let client = Client::new("token").with_transport(Paypal::default());
+        let started = start(&client);
+        assert!(started.approval_url.contains("EC-2JT80165HL3458339"));
+        let url = format!(
+            "https://mysite.com/return?paymentId={}&token=EC-2JT80165HL3458339&PayerID=QQGSRNHDACTLJ",
+            PAYMENT_ID
+        );
+        assert!(matches!(
+            finish(&client, &started.state, &url).unwrap(),
+            Outcome::Completed(_)
+        ));
+        assert!(matches!(
+            finish(&client, &started.state, &url).unwrap(),
+            Outcome::Replayed(_)
+        ));
+    }
+
+    #[test]
+    fn test_cancel_and_tampering() {
+        let client = Client::new("token").with_transport(Paypal::default());
+        let started = start(&client);
+        assert!(matches!(
+            finish(&client, &started.state, "token=EC-2JT80165HL3458339").unwrap(),
+            Outcome::Cancelled
+        ));
+        // without a PayerID, only the token tells a cancellation from a broken URL
+        assert!(finish(&client, &started.state, "").is_err());
+        assert!(finish(&client, &started.state, "token=EC-OTHER").is_err());
+        let other = "paymentId=PAYID-OTHER&token=EC-2JT80165HL3458339&PayerID=QQGSRNHDACTLJ";
+        assert!(finish(&client, &started.state, other).is_err());
+        assert!(finish(&client, "garbage", "token=EC-2JT80165HL3458339").is_err());
+        assert!(finish_order(&client, &started.state, "token=EC-2JT80165HL3458339").is_err());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_order_checkout() {
+        use crate::types::{Money, OrderStatus};
+
+        let server = crate::testing::MockServer::start();
+        let client = server.client();
+        let amount = Money {
+            currency_code: "USD".to_string(),
+            value: "100.00".to_string(),
+            ..Default::default()
+        };
+        let started = start_order(
+            &client,
+            "https://mysite.com/return",
+            "https://mysite.com/cancel",
+            OrderIntent::Capture,
+            vec![PurchaseUnit {
+                amount,
+                ..Default::default()
+            }],
+        )
+        .unwrap();
+        let id = &started.created.id;
+        let cancelled = format!("https://mysite.com/cancel?token={}", id);
+        assert!(matches!(
+            finish_order(&client, &started.state, &cancelled).unwrap(),
+            Outcome::Cancelled
+        ));
+        assert!(finish_order(&client, &started.state, "token=OTHER").is_err());
+        assert!(finish(&client, &started.state, &cancelled).is_err());
+
+        let url = server.approve_order(id, "QQGSRNHDACTLJ").unwrap();
+        assert!(url.starts_with("https://mysite.com/return?token="));
+        match finish_order(&client, &started.state, &url).unwrap() {
+            Outcome::Completed(order) => assert_eq!(Some(OrderStatus::Completed), order.status),
+            outcome => panic!("{:?}", outcome),
+        }
+        assert!(matches!(
+            finish_order(&client, &started.state, &url).unwrap(),
+            Outcome::Replayed(_)
+        ));
+    }
+}
@@ -0,0 +1,453 @@
+use crate::credentials::{AccessToken, Credentials};
+use crate::error::Error;
+use crate::metrics::MetricsHook;
+use crate::middleware::Middleware;
+use crate::rate_limit::RateLimiter;
+use crate::request::Resp;
+use crate::response::{Metadata, Response};
+use crate::token_store::{ManagedToken, MemoryTokenStore, TokenStore};
+use crate::transport::{ReqwestTransport, Transport};
+use crate::types::{LinkDescription, Scopes};
+use base64::{encode_config, URL_SAFE_NO_PAD};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Holds the bearer token, and any extra headers, that every call made through it sends to
+/// paypal. Obtain the bearer token using the `get_token` function, or let the client request and
+/// refresh its own with `Client::from_credentials`.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, payment, Client};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let client = Client::new(&token.access_token)
+///     .with_auth_assertion(&paypal::auth_assertion("my_id", "MERCHANT_PAYER_ID"))
+///     .with_partner_attribution_id("MY_BN_CODE");
+/// let list = payment::list(&client).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Client {
+    auth: Auth,
+    base_url: String,
+    headers: HashMap<String, String>,
+    metadata: Option<Arc<Mutex<Option<Metadata>>>>,
+    transport: Arc<dyn Transport>,
+    timeout: Option<Duration>,
+    log_bodies: bool,
+    metrics: Vec<Arc<dyn MetricsHook>>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("auth", &self.auth)
+            .field("base_url", &self.base_url)
+            .field("headers", &self.headers)
+            .field("timeout", &self.timeout)
+            .field("log_bodies", &self.log_bodies)
+            .field("rate_limiter", &self.rate_limiter)
+            .finish_non_exhaustive()
+    }
+}
+
+// how a client authenticates: with a token it was given, or with one it requests itself
+#[derive(Clone)]
+enum Auth {
+    Bearer(AccessToken),
+    Managed(Arc<ManagedToken>),
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Auth::Bearer(token) => f.debug_tuple("Bearer").field(token).finish(),
+            Auth::Managed(managed) => f
+                .debug_tuple("Managed")
+                .field(managed.credentials())
+                .finish(),
+        }
+    }
+}
+
+impl Client {
+    /// Creates a client that authenticates using the given bearer token, such as the
+    /// `AccessToken` returned by `get_token`.
+    pub fn new<T: AsRef<str>>(bearer: T) -> Self {
+        Self::with_auth(Auth::Bearer(AccessToken::new(bearer.as_ref().into())))
+    }
+
+    /// Creates a client that requests its own access token with `credentials` when first
+    /// needed, and requests a new one shortly before it expires. Clones of the client share the
+    /// token, and so do clients sharing a `TokenStore`. A call PayPal refuses with `401
+    /// Unauthorized`, for example because the token was revoked, is retried once with another.
+    ///
+    /// ```rust,no_run
+    /// use paypal::{payment, Client, Credentials};
+    ///
+    /// let client = Client::from_credentials(Credentials::from_env().unwrap());
+    /// let list = payment::list(&client).unwrap();
+    /// ```
+    pub fn from_credentials(credentials: Credentials) -> Self {
+        let store = Arc::new(MemoryTokenStore::new());
+        Self::with_auth(Auth::Managed(Arc::new(ManagedToken::new(
+            credentials,
+            store,
+        ))))
+    }
+
+    fn with_auth(auth: Auth) -> Self {
+        Self {
+            auth,
+            base_url: crate::_ADDR.into(),
+            headers: HashMap::new(),
+            metadata: None,
+            transport: Arc::new(ReqwestTransport::default()),
+            timeout: None,
+            log_bodies: false,
+            metrics: Vec::new(),
+            middleware: Vec::new(),
+            rate_limiter: None,
+        }
+    }
+
+    /// Sends every call to `base_url` instead of PayPal's live or sandbox API, for example to
+    /// a mock server in tests.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').into();
+        self
+    }
+
+    /// Sends every call through `transport` instead of the default `ReqwestTransport`.
+    pub fn with_transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    /// Gives up on every call whose complete response takes longer than `timeout`, overriding the
+    /// total timeout of the transport. Being cheap to clone, a client can be narrowed down for a
+    /// single call.
+    ///
+    /// ```rust,no_run
+    /// use paypal::{payment, Client};
+    /// use std::time::Duration;
+    ///
+    /// let client = Client::new("token");
+    /// let quick = client.clone().with_timeout(Duration::from_secs(2));
+    /// let list = payment::list(&quick).unwrap();
+    /// ```
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Logs the body of every request and response as a `DEBUG` event of the call's `tracing`
+    /// span. Tokens, card numbers and CVVs are redacted, but other personal data of payers is
+    /// not, so keep this off in production.
+    pub fn with_body_logging(mut self) -> Self {
+        self.log_bodies = true;
+        self
+    }
+
+    /// Reports every exchange made through this client to `hook`, in addition to any hooks
+    /// installed before.
+    pub fn with_metrics<H: MetricsHook + 'static>(mut self, hook: H) -> Self {
+        self.metrics.push(Arc::new(hook));
+        self
+    }
+
+    /// Passes every request made through this client, and its response, through `middleware`.
+    /// Middleware added first sees the request first and the response last.
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Paces every call made through this client with `limiter`, and retries the calls PayPal
+    /// rejects with `429 Too Many Requests`. See `RateLimiter`.
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Keeps the access token of a client created with `from_credentials` in `store` instead of
+    /// in memory, for example to share it between processes. Has no effect on a client created
+    /// with `new`.
+    pub fn with_token_store<S: TokenStore + 'static>(mut self, store: S) -> Self {
+        if let Auth::Managed(managed) = &self.auth {
+            let credentials = managed.credentials().clone();
+            self.auth = Auth::Managed(Arc::new(ManagedToken::new(credentials, Arc::new(store))));
+        }
+        self
+    }
+
+    /// Sends a `PayPal-Auth-Assertion` header with every call, so that a partner can act on
+    /// behalf of a connected merchant. See `auth_assertion` for building the value.
+    pub fn with_auth_assertion(self, assertion: &str) -> Self {
+        self.with_header("PayPal-Auth-Assertion", assertion)
+    }
+
+    /// Sends a `PayPal-Partner-Attribution-Id` header with every call, which attributes the
+    /// calls to the partner's BN code.
+    pub fn with_partner_attribution_id(self, attribution_id: &str) -> Self {
+        self.with_header("PayPal-Partner-Attribution-Id", attribution_id)
+    }
+
+    /// Sends an arbitrary header with every call.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Runs `call` and returns its result together with the status, headers and raw body of the
+    /// response it was deserialized from. When `call` makes several requests, the last one
+    /// counts, even if it failed and `call` recovered. When `call` fails, its error carries the
+    /// status and `Error::debug_id` of the response instead.
+    ///
+    /// ```rust,no_run
+    /// use paypal::{get_token, payment, Client};
+    ///
+    /// let token = get_token("my_id", "my_secret").unwrap();
+    /// let client = Client::new(&token.access_token);
+    /// let response = client.with_response(|client| payment::list(client)).unwrap();
+    /// println!("{} payments, debug id {:?}", response.value.count, response.debug_id);
+    /// ```
+    pub fn with_response<T, F>(&self, call: F) -> Resp<Response<T>>
+    where
+        F: FnOnce(&Client) -> Resp<T>,
+    {
+        let slot = Arc::new(Mutex::new(None));
+        let client = Self {
+            metadata: Some(slot.clone()),
+            ..self.clone()
+        };
+        let value = call(&client)?;
+        let metadata = slot.lock().unwrap().take().unwrap_or_default();
+        Ok(metadata.with_value(value))
+    }
+
+    /// Runs `call` and returns its result together with the raw body of the response it was
+    /// deserialized from, for example to store PayPal's reply verbatim for auditing. A shorthand
+    /// for `with_response`.
+    ///
+    /// ```rust,no_run
+    /// use paypal::{get_token, payment, Client};
+    ///
+    /// let token = get_token("my_id", "my_secret").unwrap();
+    /// let client = Client::new(&token.access_token);
+    /// let (list, raw) = client.with_raw_body(|client| payment::list(client)).unwrap();
+    /// ```
+    pub fn with_raw_body<T, F>(&self, call: F) -> Resp<(T, String)>
+    where
+        F: FnOnce(&Client) -> Resp<T>,
+    {
+        let response = self.with_response(call)?;
+        Ok((response.value, response.raw_body))
+    }
+
+    /// Follows a HATEOAS link, such as the `self` link of a payment, issuing the link's HTTP
+    /// method without a body and deserializing the response into the type of the caller's
+    /// choice.
+    ///
+    /// Links to another scheme, host or port than the client's base url are refused with an
+    /// error, so that the access token is never sent anywhere else.
+    ///
+    /// ```rust,no_run
+    /// use paypal::{get_token, payment, Client, LinkRel, Payment};
+    ///
+    /// let token = get_token("my_id", "my_secret").unwrap();
+    /// let client = Client::new(&token.access_token);
+    /// let list = payment::list(&client).unwrap();
+    /// let link = list.payments[0].link(&LinkRel::SelfLink).unwrap();
+    /// let refreshed: Payment = client.follow(link).unwrap();
+    /// ```
+    pub fn follow<T>(&self, link: &LinkDescription) -> Resp<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let href = self.link_href(link)?;
+        crate::request::call::<(), T>(self, link_method(link)?, href, None)
+    }
+
+    /// Follows a HATEOAS link like `follow`, sending `body` as JSON, for example the `payer_id`
+    /// when following the `execute` link of a payment.
+    pub fn follow_with<F, T>(&self, link: &LinkDescription, body: &F) -> Resp<T>
+    where
+        F: serde::Serialize + ?Sized,
+        T: serde::de::DeserializeOwned,
+    {
+        let href = self.link_href(link)?;
+        crate::request::call(self, link_method(link)?, href, Some(body))
+    }
+
+    // the href of a link, if it points at the same origin as the base url
+    fn link_href<'a>(&self, link: &'a LinkDescription) -> Resp<&'a str> {
+        let parse = |url: &str| {
+            reqwest::Url::parse(url)
+                .map_err(|err| Error::new(format!("invalid url `{}`: {}", url, err)))
+        };
+        if parse(&link.href)?.origin() != parse(&self.base_url)?.origin() {
+            return Err(Error::new(format!(
+                "refusing to follow the `{}` link to {}, which is not under {}",
+                link.rel, link.href, self.base_url
+            )));
+        }
+        Ok(&link.href)
+    }
+
+    // only builds the metadata when running inside `with_response`
+    pub(crate) fn record_metadata<F: FnOnce() -> Metadata>(&self, metadata: F) {
+        if let Some(slot) = &self.metadata {
+            *slot.lock().unwrap() = Some(metadata());
+        }
+    }
+
+    pub(crate) fn transport(&self) -> &dyn Transport {
+        self.transport.as_ref()
+    }
+
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub(crate) fn logs_bodies(&self) -> bool {
+        self.log_bodies
+    }
+
+    pub(crate) fn metrics(&self) -> &[Arc<dyn MetricsHook>] {
+        &self.metrics
+    }
+
+    pub(crate) fn middleware(&self) -> &[Arc<dyn Middleware>] {
+        &self.middleware
+    }
+
+    pub(crate) fn token_scopes(&self) -> Option<Scopes> {
+        match &self.auth {
+            Auth::Bearer(_) => None,
+            Auth::Managed(managed) => managed.scopes(),
+        }
+    }
+
+    pub(crate) fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+
+    pub(crate) fn endpoint(&self, ep: &str) -> String {
+        format!("{}{}", self.base_url, ep)
+    }
+
+    // after PayPal refused the access token `rejected`, another one to retry with, for a client
+    // that requests its own
+    pub(crate) fn replace_token(&self, rejected: &str) -> Resp<Option<AccessToken>> {
+        match &self.auth {
+            Auth::Bearer(_) => Ok(None),
+            Auth::Managed(managed) => managed.replace(self, rejected).map(Some),
+        }
+    }
+
+    pub(crate) fn headers(&self) -> Resp<HashMap<String, String>> {
+        let token = match &self.auth {
+            Auth::Bearer(token) => token.clone(),
+            Auth::Managed(managed) => managed.get(self)?,
+        };
+        let mut headers = self.headers.clone();
+        headers.insert(
+            "Authorization".into(),
+            format!("Bearer {}", token.expose_secret()),
+        );
+        Ok(headers)
+    }
+}
+
+// Links without a method are plain GETs. `REDIRECT` links are meant for the payer's browser.
+fn link_method(link: &LinkDescription) -> Resp<reqwest::Method> {
+    let method = link.method.as_deref().unwrap_or("GET");
+    match method {
+        "REDIRECT" => Err(Error::new(format!(
+            "the `{}` link is a redirect for the payer, not an API call",
+            link.rel
+        ))),
+        method => reqwest::Method::from_bytes(method.as_bytes())
+            .map_err(|_| Error::new(format!("unsupported link method `{}`", method))),
+    }
+}
+
+/// Builds the unsigned JWT that PayPal expects in the `PayPal-Auth-Assertion` header, identifying
+/// the partner by its `client_id` and the connected merchant by its `merchant_payer_id`.
+pub fn auth_assertion(client_id: &str, merchant_payer_id: &str) -> String {
+    let header = serde_json::json!({ "alg": "none" });
+    let payload = serde_json::json!({ "iss": client_id, "payer_id": merchant_payer_id });
+    format!(
+        "{}.{}.",
+        encode_config(&header.to_string(), URL_SAFE_NO_PAD),
+        encode_config(&payload.to_string(), URL_SAFE_NO_PAD)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpRequest, HttpResponse};
+
+    #[test]
+    fn test_auth_assertion() {
+        assert_eq!(
+            "eyJhbGciOiJub25lIn0.eyJpc3MiOiJteV9pZCIsInBheWVyX2lkIjoiTUVSQ0hBTlQifQ.",
+            auth_assertion("my_id", "MERCHANT")
+        )
+    }
+
+    #[test]
+    fn test_headers() {
+        let headers = Client::new("token")
+            .with_partner_attribution_id("BN_CODE")
+            .headers()
+            .unwrap();
+        assert_eq!("Bearer token", headers["Authorization"]);
+        assert_eq!("BN_CODE", headers["PayPal-Partner-Attribution-Id"]);
+    }
+
+    #[test]
+    fn test_link_method() {
+        let mut link: LinkDescription = serde_json::from_value(serde_json::json!({
+            "href": "https://api.sandbox.paypal.com/v1/payments/payment/PAYID-LZ7ZYRA",
+            "rel": "self"
+        }))
+        .unwrap();
+        assert_eq!(reqwest::Method::GET, link_method(&link).unwrap());
+        link.method = Some("REDIRECT".to_string());
+        assert!(link_method(&link).is_err());
+    }
+
+    #[test]
+    fn test_follow_refuses_other_hosts() {
+        struct Unreachable;
+
+        impl Transport for Unreachable {
+            fn send(&self, _: &HttpRequest) -> Result<HttpResponse, Error> {
+                panic!("the request should not have been sent");
+            }
+        }
+
+        let client = Client::new("token")
+            .with_base_url("https://api.sandbox.paypal.com")
+            .with_transport(Unreachable);
+        for href in &[
+            "https://attacker.example/v1/payments/payment/PAYID-LZ7ZYRA",
+            "http://api.sandbox.paypal.com/v1/payments/payment/PAYID-LZ7ZYRA",
+            "https://api.sandbox.paypal.com.attacker.example/v1/payments/payment",
+            "not a url",
+        ] {
+            let link: LinkDescription = serde_json::from_value(serde_json::json!({
+                "href": href,
+                "rel": "self"
+            }))
+            .unwrap();
+            assert!(client.follow::<serde_json::Value>(&link).is_err());
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::error::Error;
+use crate::redact::REDACTED;
+use crate::types::Scope;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// A REST app's client ID and secret. The secret is wiped from memory once the credentials are
+/// dropped, and is never part of their `Debug` output.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, Credentials};
+///
+/// let credentials = Credentials::from_env().unwrap();
+/// let token = get_token(credentials.client_id(), credentials.expose_secret()).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Credentials {
+    client_id: String,
+    secret: Zeroizing<String>,
+    scopes: Vec<Scope>,
+}
+
+impl Credentials {
+    /// Credentials with the given client ID and secret.
+    pub fn new(client_id: &str, secret: String) -> Self {
+        Self {
+            client_id: client_id.into(),
+            secret: Zeroizing::new(secret),
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Reads the credentials from the `PAYPAL_CLIENT_ID` and `PAYPAL_SECRET` environment
+    /// variables.
+    pub fn from_env() -> Result<Self, Error> {
+        let var =
+            |key: &str| std::env::var(key).map_err(|_| Error::new(format!("{} is not set", key)));
+        Ok(Self::new(&var("PAYPAL_CLIENT_ID")?, var("PAYPAL_SECRET")?))
+    }
+
+    /// Reads the credentials from a file of `KEY=value` lines setting `PAYPAL_CLIENT_ID` and
+    /// `PAYPAL_SECRET`. Blank lines, `#` comments and other keys are ignored.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = read(path)?;
+        let mut values = parse(&file);
+        let mut value = |key: &str| {
+            values
+                .remove(key)
+                .ok_or_else(|| Error::new(format!("{} is not set in {}", key, path.display())))
+        };
+        let client_id = value("PAYPAL_CLIENT_ID")?;
+        Ok(Self::new(&client_id, value("PAYPAL_SECRET")?))
+    }
+
+    /// Reads the credentials from the environment like `from_env`, taking any variable that is
+    /// not set from a file like `from_file`. The file is only read when a variable is missing.
+    pub fn from_env_or_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::load(|key| std::env::var(key).ok(), path.as_ref())
+    }
+
+    // `from_env_or_file`, reading the environment through `var`
+    fn load<V: Fn(&str) -> Option<String>>(var: V, path: &Path) -> Result<Self, Error> {
+        let (client_id, secret) = match (var("PAYPAL_CLIENT_ID"), var("PAYPAL_SECRET")) {
+            (Some(client_id), Some(secret)) => return Ok(Self::new(&client_id, secret)),
+            vars => vars,
+        };
+        let file = read(path)?;
+        let mut values = parse(&file);
+        let mut value = |key: &str, var: Option<String>| {
+            var.or_else(|| values.remove(key)).ok_or_else(|| {
+                Error::new(format!(
+                    "{} is not set in the environment or in {}",
+                    key,
+                    path.display()
+                ))
+            })
+        };
+        let client_id = value("PAYPAL_CLIENT_ID", client_id)?;
+        Ok(Self::new(&client_id, value("PAYPAL_SECRET", secret)?))
+    }
+
+    /// Asks for tokens granting only `scopes`, instead of every scope of the app, when a client
+    /// created with `Client::from_credentials` requests one.
+    pub fn with_scopes(mut self, scopes: &[Scope]) -> Self {
+        self.scopes = scopes.to_vec();
+        self
+    }
+
+    /// The client ID.
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// The secret, to be handed straight to a call such as `get_token`.
+    pub fn expose_secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// The scopes asked for, or none to ask for every scope of the app.
+    pub fn scopes(&self) -> &[Scope] {
+        &self.scopes
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("client_id", &self.client_id)
+            .field("secret", &REDACTED)
+            .field("scopes", &self.scopes)
+            .finish()
+    }
+}
+
+fn read(path: &Path) -> Result<Zeroizing<String>, Error> {
+    std::fs::read_to_string(path)
+        .map(Zeroizing::new)
+        .map_err(|err| Error::new(format!("cannot read {}: {}", path.display(), err)))
+}
+
+// `KEY=value` lines, with blank lines, `#` comments and quoted values allowed
+fn parse(file: &str) -> HashMap<&str, String> {
+    file.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            Some((key.trim(), value.to_string()))
+        })
+        .collect()
+}
+
+/// An OAuth access token. It is wiped from memory once dropped, and its `Debug` and `Display`
+/// output never shows it. Serializing it, for example to cache it, does write the token itself.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, Client};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// println!("{}", token.access_token); // prints REDACTED
+/// let client = Client::new(&token.access_token);
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct AccessToken(Zeroizing<String>);
+
+impl AccessToken {
+    /// Wraps a token obtained elsewhere.
+    pub fn new(token: String) -> Self {
+        AccessToken(Zeroizing::new(token))
+    }
+
+    /// The token itself, for example to send it in an `Authorization` header.
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for AccessToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("AccessToken").field(&REDACTED).finish()
+    }
+}
+
+impl fmt::Display for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for AccessToken {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for AccessToken {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(AccessToken::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_are_not_printed() {
+        let credentials = Credentials::new("AYSq3RDGsmBLJE", "EGnHDxD_qRPdaLd".into());
+        assert!(!format!("{:?}", credentials).contains("EGnHDxD_qRPdaLd"));
+        let token = AccessToken::new("A21AAF".into());
+        assert_eq!("REDACTED", token.to_string());
+        assert_eq!("AccessToken(\"REDACTED\")", format!("{:?}", token));
+        assert_eq!("\"A21AAF\"", serde_json::to_string(&token).unwrap());
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join(format!("paypal-credentials-{}", std::process::id()));
+        std::fs::write(
+            &path,
+            "# sandbox\nPAYPAL_CLIENT_ID=AYSq3RDGsmBLJE\nPAYPAL_SECRET = \"EGnHDxD_qRPdaLd\"\n",
+        )
+        .unwrap();
+        let credentials = Credentials::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!("AYSq3RDGsmBLJE", credentials.client_id());
+        assert_eq!("EGnHDxD_qRPdaLd", credentials.expose_secret());
+    }
+
+    #[test]
+    fn test_environment_wins_over_the_file() {
+        let path = std::env::temp_dir().join(format!("paypal-env-or-file-{}", std::process::id()));
+        let missing = path.with_extension("missing");
+        let env = |key: &str| match key {
+            "PAYPAL_SECRET" => Some("from_env".to_string()),
+            _ => None,
+        };
+        let err = Credentials::load(env, &missing).unwrap_err();
+        assert!(err.to_string().starts_with("cannot read"));
+
+        std::fs::write(
+            &path,
+            "PAYPAL_CLIENT_ID=AYSq3RDGsmBLJE\nPAYPAL_SECRET=from_file\n",
+        )
+        .unwrap();
+        let credentials = Credentials::load(env, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!("AYSq3RDGsmBLJE", credentials.client_id());
+        assert_eq!("from_env", credentials.expose_secret());
+
+        let env = |_: &str| Some("from_env".to_string());
+        let credentials = Credentials::load(env, &missing).unwrap();
+        assert_eq!("from_env", credentials.client_id());
+    }
+}
@@ -1,4 +1,7 @@
+use crate::types::Scope;
 use serde::Deserialize;
+use std::fmt;
+use std::time::Duration;
 
 /// Denotes the way creating a payment can fail.
 #[derive(Debug, Deserialize)]
@@ -10,24 +13,172 @@ pub struct Error {
     /// If the cause of the error is a bad http reponse, this is the remote url
     /// (either starting with https://api.sandbox.paypal.com or https://api.paypal.com/v1)
     remote: Option<String>,
+    /// Whether the request was abandoned because it took too long
+    #[serde(default)]
+    timeout: bool,
+    /// The scope the access token lacks for the call that failed, when that is why it failed
+    #[serde(default)]
+    missing_scope: Option<Scope>,
+    /// The `Paypal-Debug-Id` of the response, which PayPal support asks for
+    #[serde(default)]
+    debug_id: Option<String>,
 }
 
+impl Error {
+    pub(crate) fn from_response(
+        request: &crate::transport::HttpRequest,
+        response: &crate::transport::HttpResponse,
+    ) -> Self {
+        // PayPal repeats the header in the `debug_id` of its error bodies
+        let debug_id = response
+            .headers
+            .get("paypal-debug-id")
+            .cloned()
+            .or_else(|| {
+                let body: serde_json::Value = serde_json::from_slice(&response.body).ok()?;
+                body["debug_id"].as_str().map(String::from)
+            });
+        Self {
+            message: String::from_utf8_lossy(&response.body).into_owned(),
+            status: Some(response.status),
+            remote: Some(request.url.clone()),
+            timeout: false,
+            missing_scope: None,
+            debug_id,
+        }
+    }
+
+    pub(crate) fn timed_out(url: &str, after: Duration) -> Self {
+        Self {
+            message: format!("no complete response within {:?}", after),
+            status: None,
+            remote: Some(url.into()),
+            timeout: true,
+            missing_scope: None,
+            debug_id: None,
+        }
+    }
+
+    pub(crate) fn new(message: String) -> Self {
+        Self {
+            message,
+            status: None,
+            remote: None,
+            timeout: false,
+            missing_scope: None,
+            debug_id: None,
+        }
+    }
+
+    // the message explains which scopes the failed call accepts; `missing` when the token has
+    // none of them
+    pub(crate) fn with_scope_hint(mut self, accepted: &[Scope], missing: bool) -> Self {
+        let names = accepted
+            .iter()
+            .map(|scope| format!("`{}`", scope))
+            .collect::<Vec<_>>()
+            .join(" or ");
+        if missing {
+            self.message += &format!(
+                " (the access token lacks the {} scope this endpoint needs)",
+                names
+            );
+            self.missing_scope = accepted.first().cloned();
+        } else {
+            self.message += &format!(
+                " (this endpoint needs the {} scope, check that the access token has it)",
+                names
+            );
+        }
+        self
+    }
+
+    /// A error message with information about why the request failed
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// If the cause of the error is a bad http reponse, this is the status code
+    pub fn status(&self) -> Option<u16> {
+        self.status
+    }
+
+    /// If the cause of the error is a bad http reponse, this is the remote url
+    pub fn remote(&self) -> Option<&str> {
+        self.remote.as_deref()
+    }
+
+    /// Whether the request failed because PayPal did not answer in time
+    pub fn is_timeout(&self) -> bool {
+        self.timeout
+    }
+
+    /// If PayPal refused the call and the access token has none of the scopes the endpoint
+    /// accepts, this is the scope to ask for. Only known for clients created with
+    /// `Client::from_credentials`.
+    pub fn missing_scope(&self) -> Option<&Scope> {
+        self.missing_scope.as_ref()
+    }
+
+    /// If PayPal answered with an error, the `Paypal-Debug-Id` of its response, which PayPal
+    /// support asks for when investigating a failed call
+    pub fn debug_id(&self) -> Option<&str> {
+        self.debug_id.as_deref()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.status, &self.remote) {
+            (Some(status), Some(remote)) => write!(f, "{} ({}): {}", remote, status, self.message),
+            (None, Some(remote)) if self.timeout => write!(f, "{}: {}", remote, self.message),
+            _ => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 impl From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
         Self {
             message: err.to_string(),
             status: err.status().map(|status| status.as_u16()),
             remote: err.url().map(|url| url.to_string()),
+            timeout: err.is_timeout(),
+            missing_scope: None,
+            debug_id: None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self {
+            message: err.to_string(),
+            status: None,
+            remote: None,
+            timeout: false,
+            missing_scope: None,
+            debug_id: None,
         }
     }
 }
 
 impl From<reqwest::Response> for Error {
     fn from(mut response: reqwest::Response) -> Self {
+        let debug_id = response
+            .headers()
+            .get("paypal-debug-id")
+            .and_then(|id| id.to_str().ok())
+            .map(String::from);
         Self {
-            message: response.text().unwrap(),
+            message: response.text().unwrap_or_default(),
             status: Some(response.status().as_u16()),
             remote: Some(response.url().to_string()),
+            timeout: false,
+            missing_scope: None,
+            debug_id,
         }
     }
 }
@@ -5,35 +5,106 @@
 //!
 //! Creating a payment is done using the `create_payment` function.
 //! ```rust,no_run
+//! # fn main() -> Result<(), paypal::error::Error> {
+//! use paypal::{payment, Client, PaymentCollection, PaymentIntent, PaymentMethod, Transaction, TransactionAmount};
+//!
 //! let token = paypal::get_token("my_id", "my_secret")?;
+//! let client = Client::new(&token.access_token);
 //! let amount = TransactionAmount {
 //!     currency: "USD".to_string(),
-//!     total: "100.00".to_string()
+//!     total: "100.00".to_string(),
+//!     ..Default::default()
 //! };
 //! let new_payment = payment::new(
-//!     &token.access_token,
+//!     &client,
 //!     "mysite.com/whooyoupaid",
 //!     "mysite.com/nooyoufailed",
 //!     PaymentMethod::Paypal,
 //!     PaymentIntent::Sale,
-//!     vec![Transaction { amount }],
+//!     vec![Transaction { amount, ..Default::default() }],
 //! )?;
+//! # Ok(())
+//! # }
 //! ```
 
+#[macro_use]
+mod macros;
+
+/// Looking up and refunding the payments captured for v2 orders
+pub mod capture;
+/// Recording and replaying HTTP exchanges, so that tests run offline
+#[cfg(any(test, feature = "testing"))]
+pub mod cassette;
+/// Sending payers to PayPal to approve a payment, and finishing the payment once they are back
+pub mod checkout;
+mod client;
+mod credentials;
 /// Possible ways that a paypal request can fail
 pub mod error;
-mod payment;
+/// Hooks reporting the latency and outcome of every exchange with PayPal
+pub mod metrics;
+/// Middleware that can change, inspect or answer the requests a `Client` makes
+pub mod middleware;
+/// Creating v2 checkout orders, including orders paid to connected sellers
+pub mod order;
+/// Walking through list endpoints page by page
+pub mod pagination;
+/// Onboarding sellers as a PayPal partner and checking their integration status
+pub mod partner_referral;
+/// Creating, listing and executing payments
+pub mod payment;
+/// Pacing the calls a `Client` makes, to stay below PayPal's rate limits
+pub mod rate_limit;
+/// Comparing local order records with the payments, captures, refunds and transactions PayPal
+/// has, to find the ones that disagree
+pub mod reconcile;
+mod redact;
 mod request;
+mod response;
+/// Looking up and refunding the sales of executed v1 payments
+pub mod sale;
+/// A mock PayPal server for testing code that uses this crate without network access
+#[cfg(feature = "testing")]
+pub mod testing;
 mod token;
+/// Keeping access tokens between calls and processes, so that they are requested only when needed
+pub mod token_store;
+/// Uploading and updating shipment tracking information
+pub mod tracking;
+/// Searching the transactions of the account, such as sales, captures and refunds
+pub mod transaction;
+/// The HTTP transport used by a `Client`, and how to plug in your own
+pub mod transport;
+/// Checking payment details locally, before sending them to PayPal
+pub mod validation;
+/// Managing payment experience web profiles, which brand the PayPal checkout pages
+pub mod web_profile;
+/// Verifying that webhook events were sent by PayPal
+pub mod webhook;
 
 mod types;
 
+pub use client::{auth_assertion, Client};
+pub use credentials::{AccessToken, Credentials};
 pub use payment::*;
-pub use token::get_token;
+pub use response::Response;
+pub use token::{
+    get_token, get_token_from, get_token_scoped, get_token_scoped_using, get_token_using,
+};
 pub use types::{
-    Address, ApplicationContext, CreditCard, CreditCardToken, FailureReason, FundingInstrument,
-    LinkDescription, ListPaymentResponse, NormalizationStatus, Payer, PayerStatus, Payment,
-    PaymentIntent, PaymentMethod, RedirectUrls, State, Transaction, TransactionAmount,
+    Address, ApplicationContext, BatchError, Capture, CaptureRefund, CaptureStatus, CardBrand,
+    Carrier, CountryCode, CreatedWebProfile, CreditCard, CreditCardToken, FailureReason,
+    FlowConfig, FundingInstrument, InputFields, IntegrationPreference, LegalConsent,
+    LinkDescription, LinkRel, ListPaymentResponse, MerchantCapability, MerchantIntegration,
+    MerchantProduct, Money, NormalizationStatus, Order, OrderApplicationContext, OrderIntent,
+    OrderPayee, OrderRequest, OrderStatus, OrderTracker, PartnerConfigOverride, PartnerReferral,
+    PartnerReferralDetails, PartnerReferralLinks, PatchOp, PatchOperation, PatchPath, Payee, Payer,
+    PayerStatus, Payment, PaymentCollection, PaymentInstruction, PaymentIntent, PaymentMethod,
+    PlatformFee, Presentation, PurchaseUnit, RedirectUrls, ReferralOperation, Refund, RefundStatus,
+    RequestNewPayment, ResponseAccessToken, RestApiIntegration, Sale, Scope, Scopes, State,
+    ThirdPartyDetails, Tracker, TrackerIdentifier, TrackerStatus, TrackersBatchResponse,
+    Transaction, TransactionAmount, TransactionDetails, TransactionInfo, TransactionSearch,
+    TransactionStatus, VerificationStatus, WebProfile, WebhookSignature,
 };
 
 #[cfg(feature = "test-mode")]
@@ -41,7 +112,3 @@ const _ADDR: &str = "https://api.sandbox.paypal.com";
 
 #[cfg(not(feature = "test-mode"))]
 const _ADDR: &str = "https://api.paypal.com";
-
-fn _make_endpoint(ep: &str) -> String {
-    format!("{}{}", _ADDR, ep)
-}
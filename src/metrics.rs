@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+/// One exchange with PayPal, as reported to a `MetricsHook`.
+#[derive(Clone, Debug)]
+pub struct Exchange<'a> {
+    /// The HTTP method, for example `POST`.
+    pub method: &'a str,
+    /// The path called, with ids replaced by `{id}`, for example `/v1/payments/payment/{id}`.
+    pub endpoint: &'a str,
+    /// The HTTP status PayPal answered with, or `None` when no response was received.
+    pub status: Option<u16>,
+    /// How long the exchange took.
+    pub latency: Duration,
+    /// 1 for the first attempt of a call, higher when the call is retried.
+    pub attempt: u32,
+}
+
+impl Exchange<'_> {
+    /// Whether this exchange repeats an earlier, failed, attempt of the same call.
+    pub fn is_retry(&self) -> bool {
+        self.attempt > 1
+    }
+
+    /// Whether this exchange requested a new access token.
+    pub fn is_token_refresh(&self) -> bool {
+        self.endpoint == TOKEN_ENDPOINT
+    }
+
+    /// Whether PayPal answered with an error status, or did not answer at all.
+    pub fn is_error(&self) -> bool {
+        self.status.map_or(true, |status| status >= 400)
+    }
+}
+
+pub(crate) const TOKEN_ENDPOINT: &str = "/v1/oauth2/token";
+
+/// Receives every exchange a `Client` has with PayPal, for example to feed latency histograms and
+/// error-rate alerts. Install one with `Client::with_metrics`.
+///
+/// ```rust
+/// use paypal::metrics::{Exchange, MetricsHook};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// #[derive(Default)]
+/// struct ErrorCount(AtomicUsize);
+///
+/// impl MetricsHook for ErrorCount {
+///     fn exchange(&self, exchange: &Exchange) {
+///         if exchange.is_error() {
+///             self.0.fetch_add(1, Ordering::Relaxed);
+///         }
+///     }
+/// }
+///
+/// let client = paypal::Client::new("token").with_metrics(ErrorCount::default());
+/// ```
+///
+/// With the `metrics` feature, every exchange, including token requests, is also reported to the
+/// `metrics` crate as `paypal_requests_total`, `paypal_request_duration_seconds`,
+/// `paypal_retries_total` and `paypal_token_refreshes_total`, labelled by method, endpoint and
+/// status.
+pub trait MetricsHook: Send + Sync {
+    /// Called once an exchange is over, whether it succeeded or not.
+    fn exchange(&self, exchange: &Exchange);
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn record(exchange: &Exchange) {
+    let status = exchange
+        .status
+        .map_or_else(|| "none".to_string(), |status| status.to_string());
+    let labels = [
+        ("method", exchange.method.to_string()),
+        ("endpoint", exchange.endpoint.to_string()),
+        ("status", status),
+    ];
+    ::metrics::counter!("paypal_requests_total", &labels).increment(1);
+    ::metrics::histogram!("paypal_request_duration_seconds", &labels)
+        .record(exchange.latency.as_secs_f64());
+    if exchange.is_retry() {
+        ::metrics::counter!("paypal_retries_total", &labels).increment(1);
+    }
+    if exchange.is_token_refresh() {
+        ::metrics::counter!("paypal_token_refreshes_total", &labels).increment(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpRequest, HttpResponse, Transport};
+    use crate::Client;
+    use std::sync::{Arc, Mutex};
+
+    struct Unavailable;
+
+    impl Transport for Unavailable {
+        fn send(&self, _: &HttpRequest) -> Result<HttpResponse, crate::error::Error> {
+            Ok(HttpResponse {
+                status: 503,
+                headers: Default::default(),
+                body: Vec::new(),
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorded(Arc<Mutex<Vec<String>>>);
+
+    impl MetricsHook for Recorded {
+        fn exchange(&self, exchange: &Exchange) {
+            let line = format!(
+                "{} {} {:?}",
+                exchange.method, exchange.endpoint, exchange.status
+            );
+            self.0.lock().unwrap().push(line);
+        }
+    }
+
+    #[test]
+    fn test_hook_sees_every_exchange() {
+        let hook = Recorded::default();
+        let recorded = hook.0.clone();
+        let client = Client::new("token")
+            .with_transport(Unavailable)
+            .with_metrics(hook);
+        assert!(crate::payment::execute(&client, "PAY-1B56960729604235TKQQIYVY", "PAYER").is_err());
+        assert_eq!(
+            vec!["POST /v1/payments/payment/{id}/execute Some(503)"],
+            *recorded.lock().unwrap()
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use crate::error::Error;
+use crate::transport::{HttpRequest, HttpResponse, Transport};
+use std::sync::Arc;
+
+/// Wraps the requests a `Client` sends: a middleware may change the request before passing it
+/// on with `next.run`, inspect or change the response that comes back, or answer the request
+/// itself without calling `next` at all. Install one with `Client::with_middleware`.
+///
+/// Closures taking the request and `Next` are middleware too.
+///
+/// ```rust
+/// use paypal::middleware::Next;
+/// use paypal::transport::{HttpRequest, HttpResponse};
+/// use paypal::{payment, Client};
+///
+/// let client = Client::new("token")
+///     .with_middleware(|mut request: HttpRequest, next: Next| {
+///         request.headers.insert("Prefer".into(), "return=representation".into());
+///         next.run(request)
+///     })
+///     .with_middleware(|_: HttpRequest, _: Next| {
+///         Ok(HttpResponse {
+///             status: 200,
+///             headers: Default::default(),
+///             body: br#"{"payments": [], "count": 0}"#.to_vec(),
+///         })
+///     });
+/// assert_eq!(0, payment::list(&client).unwrap().count);
+/// ```
+pub trait Middleware: Send + Sync {
+    /// Handles `request`, usually by passing it on to `next`.
+    fn handle(&self, request: HttpRequest, next: Next) -> Result<HttpResponse, Error>;
+}
+
+impl<F> Middleware for F
+where
+    F: Fn(HttpRequest, Next) -> Result<HttpResponse, Error> + Send + Sync,
+{
+    fn handle(&self, request: HttpRequest, next: Next) -> Result<HttpResponse, Error> {
+        self(request, next)
+    }
+}
+
+/// The rest of the middleware chain, ending with the client's transport.
+pub struct Next<'a> {
+    middleware: &'a [Arc<dyn Middleware>],
+    transport: &'a dyn Transport,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(middleware: &'a [Arc<dyn Middleware>], transport: &'a dyn Transport) -> Self {
+        Self {
+            middleware,
+            transport,
+        }
+    }
+
+    /// Passes `request` to the next middleware, or sends it once there is none left.
+    pub fn run(self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => first.handle(request, Next::new(rest, self.transport)),
+            None => crate::request::transmit(self.transport, &request),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Client;
+    use std::sync::Mutex;
+
+    // records the order in which middleware runs
+    fn tag(log: &Arc<Mutex<Vec<String>>>, name: &'static str) -> impl Middleware + 'static {
+        let log = log.clone();
+        move |request: HttpRequest, next: Next| {
+            log.lock().unwrap().push(format!("{} request", name));
+            let response = next.run(request);
+            log.lock().unwrap().push(format!("{} response", name));
+            response
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let client = Client::new("token")
+            .with_middleware(tag(&log, "outer"))
+            .with_middleware(tag(&log, "inner"))
+            .with_middleware(|request: HttpRequest, _: Next| {
+                assert_eq!("Bearer token", request.headers["Authorization"]);
+                Ok(HttpResponse {
+                    status: 204,
+                    headers: Default::default(),
+                    body: Vec::new(),
+                })
+            });
+        crate::web_profile::delete(&client, "XP-1234").unwrap();
+        assert_eq!(
+            vec![
+                "outer request",
+                "inner request",
+                "inner response",
+                "outer response"
+            ],
+            *log.lock().unwrap()
+        );
+    }
+}
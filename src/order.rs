@@ -0,0 +1,242 @@
+use crate::client::Client;
+use crate::request::Resp;
+use crate::types::{Order, OrderRequest, PatchOp};
+
+/// Creates a v2 order. Send the payer to `Order::approval_url` to have them approve it.
+///
+/// A partner acting for a connected seller sets the seller as the `payee` of each purchase unit,
+/// and the fees it keeps as `platform_fees`:
+///
+/// ```rust,no_run
+/// use paypal::{get_token, order, Client};
+/// use paypal::{Money, OrderIntent, OrderPayee, OrderRequest};
+/// use paypal::{PaymentInstruction, PlatformFee, PurchaseUnit};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let client = Client::new(&token.access_token);
+/// let money = |value: &str| Money {
+///     currency_code: "USD".to_string(),
+///     value: value.to_string(),
+///     ..Default::default()
+/// };
+/// let order = OrderRequest {
+///     intent: OrderIntent::Capture,
+///     purchase_units: vec![PurchaseUnit {
+///         amount: money("100.00"),
+///         payee: Some(OrderPayee {
+///             merchant_id: Some("SELLERID42".to_string()),
+///             ..Default::default()
+///         }),
+///         payment_instruction: Some(PaymentInstruction {
+///             platform_fees: vec![PlatformFee {
+///                 amount: money("5.00"),
+///                 ..Default::default()
+///             }],
+///             ..Default::default()
+///         }),
+///         ..Default::default()
+///     }],
+///     application_context: None,
+/// };
+/// let created = order::create(&client, &order).unwrap();
+/// ```
+pub fn create(client: &Client, order: &OrderRequest) -> Resp<Order> {
+    crate::request::post_json(client, &client.endpoint("/v2/checkout/orders"), order)
+}
+
+/// Shows the details of a v2 order, by ID.
+pub fn get(client: &Client, order_id: &str) -> Resp<Order> {
+    crate::request::get(
+        client,
+        &client.endpoint(&format!("/v2/checkout/orders/{}", order_id)),
+    )
+}
+
+/// Updates an order the payer has not approved yet, or approved but that was not captured, such
+/// as its amount or shipping address. PayPal replies with no content, so fetch the order again to
+/// see the result.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, order, Client, Money, PatchOp, PatchPath};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let amount = Money {
+///     currency_code: "USD".to_string(),
+///     value: "120.00".to_string(),
+///     ..Default::default()
+/// };
+/// let path = PatchPath::root()
+///     .field("purchase_units")
+///     .filter("reference_id", "default")?
+///     .field("amount");
+/// let ops = [PatchOp::replace(path, &amount)?];
+/// order::update(&Client::new(&token.access_token), "5O190127TN364715T", &ops)?;
+/// # Ok::<(), paypal::error::Error>(())
+/// ```
+pub fn update(client: &Client, order_id: &str, ops: &[PatchOp]) -> Resp<()> {
+    crate::request::patch_json(
+        client,
+        &client.endpoint(&format!("/v2/checkout/orders/{}", order_id)),
+        ops,
+    )
+}
+
+/// Captures the payment of an order the payer approved. The captures are listed in the
+/// `payments` of each purchase unit of the returned order.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, order, Client};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let captured = order::capture(&Client::new(&token.access_token), "5O190127TN364715T").unwrap();
+/// for unit in &captured.purchase_units {
+///     for capture in unit.payments.iter().flat_map(|payments| &payments.captures) {
+///         println!("{} {:?}", capture.id, capture.status);
+///     }
+/// }
+/// ```
+pub fn capture(client: &Client, order_id: &str) -> Resp<Order> {
+    crate::request::post_json(
+        client,
+        &client.endpoint(&format!("/v2/checkout/orders/{}/capture", order_id)),
+        &serde_json::json!({}),
+    )
+}
+
+/// Authorizes the payment of an order the payer approved, for an order created with the
+/// `Authorize` intent. The authorizations are listed in the `extra` fields of the `payments` of
+/// each purchase unit of the returned order.
+pub fn authorize(client: &Client, order_id: &str) -> Resp<Order> {
+    crate::request::post_json(
+        client,
+        &client.endpoint(&format!("/v2/checkout/orders/{}/authorize", order_id)),
+        &serde_json::json!({}),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        Money, OrderIntent, OrderPayee, PaymentInstruction, PlatformFee, PurchaseUnit,
+    };
+
+    fn money(value: &str) -> Money {
+        Money {
+            currency_code: "USD".to_string(),
+            value: value.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_order_serialization() {
+        let order = OrderRequest {
+            intent: OrderIntent::Capture,
+            purchase_units: vec![PurchaseUnit {
+                amount: money("100.00"),
+                payee: Some(OrderPayee {
+                    merchant_id: Some("SELLERID42".to_string()),
+                    ..Default::default()
+                }),
+                payment_instruction: Some(PaymentInstruction {
+                    platform_fees: vec![PlatformFee {
+                        amount: money("5.00"),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            application_context: None,
+        };
+        assert_eq!(
+            r#"{"intent":"CAPTURE","purchase_units":[{"amount":{"currency_code":"USD","value":"100.00"},"payee":{"merchant_id":"SELLERID42"},"payment_instruction":{"platform_fees":[{"amount":{"currency_code":"USD","value":"5.00"}}]}}]}"#,
+            serde_json::to_string(&order).unwrap()
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_create_and_capture() {
+        use crate::types::{CaptureStatus, OrderStatus};
+
+        let server = crate::testing::MockServer::start();
+        let client = server.client();
+        let order = OrderRequest {
+            intent: OrderIntent::Capture,
+            purchase_units: vec![PurchaseUnit {
+                amount: money("100.00"),
+                ..Default::default()
+            }],
+            application_context: None,
+        };
+        let created = create(&client, &order).unwrap();
+        assert!(created.approval_url().is_some());
+        assert_eq!(created.id, get(&client, &created.id).unwrap().id);
+        assert_eq!(
+            Some(422),
+            capture(&client, &created.id).unwrap_err().status()
+        );
+
+        server.approve_order(&created.id, "PAYER42").unwrap();
+        let captured = capture(&client, &created.id).unwrap();
+        assert_eq!(Some(OrderStatus::Completed), captured.status);
+        let captures = &captured.purchase_units[0]
+            .payments
+            .as_ref()
+            .unwrap()
+            .captures;
+        assert_eq!(Some(CaptureStatus::Completed), captures[0].status);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_update() {
+        use crate::types::PatchPath;
+
+        let server = crate::testing::MockServer::start();
+        let client = server.client();
+        let order = OrderRequest {
+            intent: OrderIntent::Capture,
+            purchase_units: vec![PurchaseUnit {
+                amount: money("100.00"),
+                ..Default::default()
+            }],
+            application_context: None,
+        };
+        let created = create(&client, &order).unwrap();
+        let unit = PatchPath::root()
+            .field("purchase_units")
+            .filter("reference_id", "default")
+            .unwrap();
+        let ops = [
+            PatchOp::replace(unit.clone().field("amount"), &money("120.00")).unwrap(),
+            PatchOp::add(unit.field("description"), "Two tickets").unwrap(),
+        ];
+        update(&client, &created.id, &ops).unwrap();
+        let updated = get(&client, &created.id).unwrap();
+        assert_eq!("120.00", updated.purchase_units[0].amount.value);
+        assert_eq!(
+            Some(&serde_json::json!("Two tickets")),
+            updated.purchase_units[0].extra.get("description")
+        );
+
+        let missing = [PatchOp::replace(
+            "/purchase_units/@reference_id=='other'/amount",
+            &money("1.00"),
+        )
+        .unwrap()];
+        assert_eq!(
+            Some(400),
+            update(&client, &created.id, &missing).unwrap_err().status()
+        );
+
+        server.approve_order(&created.id, "PAYER42").unwrap();
+        capture(&client, &created.id).unwrap();
+        assert_eq!(
+            Some(422),
+            update(&client, &created.id, &ops).unwrap_err().status()
+        );
+    }
+}
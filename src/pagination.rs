@@ -0,0 +1,371 @@
+use crate::error::Error;
+use crate::request::Resp;
+use crate::types::{LinkDescription, LinkRel};
+use std::collections::VecDeque;
+
+/// Where the next page of a list starts, in the pagination style of the endpoint.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cursor {
+    /// The ID of the first item of the next page, like the `next_id` of `ListPaymentResponse`.
+    StartId(String),
+    /// The number of the next page, counting from 1, for endpoints taking `page` and
+    /// `page_size` and answering with `total_pages`.
+    Page(u32),
+    /// The `next` HATEOAS link, to be followed with `Client::follow`.
+    Link(LinkDescription),
+}
+
+/// The page a `Paginator` asks its fetch function for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PageRequest {
+    /// Where the page starts, or `None` for the first page.
+    pub cursor: Option<Cursor>,
+    /// How many items to ask for, or `None` for the endpoint's default.
+    pub page_size: Option<u32>,
+}
+
+/// One page of a list, as returned by the fetch function of a `Paginator`.
+#[derive(Clone, Debug)]
+pub struct Page<T> {
+    /// The items on the page.
+    pub items: Vec<T>,
+    /// Where the next page starts, or `None` on the last page.
+    pub next: Option<Cursor>,
+}
+
+impl<T> Page<T> {
+    /// A page of `items`, followed by the page at `next`.
+    pub fn new(items: Vec<T>, next: Option<Cursor>) -> Self {
+        Self { items, next }
+    }
+
+    /// Page number `page` out of `total_pages`, counting from 1.
+    pub fn numbered(items: Vec<T>, page: u32, total_pages: u32) -> Self {
+        let next = if page < total_pages {
+            Some(Cursor::Page(page + 1))
+        } else {
+            None
+        };
+        Self { items, next }
+    }
+
+    /// A page whose `links` lead to the next one, if there is one.
+    pub fn linked(items: Vec<T>, links: &[LinkDescription]) -> Self {
+        let next = links.iter().find(|link| link.rel == LinkRel::Next);
+        Self {
+            items,
+            next: next.cloned().map(Cursor::Link),
+        }
+    }
+}
+
+type Fetch<T> = Box<dyn FnMut(&PageRequest) -> Resp<Page<T>> + Send>;
+
+/// Yields the items of a list endpoint one at a time, fetching the next page only when the
+/// items of the previous one ran out. A failed fetch is yielded as an error and ends the
+/// iteration.
+///
+/// `payment::list_all` returns one for payments. Any other list endpoint can be walked by
+/// passing a function that fetches one page:
+///
+/// ```rust,no_run
+/// use paypal::pagination::{Cursor, Page, Paginator};
+/// use paypal::{Client, LinkDescription, LinkRel};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Invoices {
+///     items: Vec<serde_json::Value>,
+///     links: Vec<LinkDescription>,
+/// }
+///
+/// let client = Client::new("token");
+/// let first = LinkDescription {
+///     href: "https://api.paypal.com/v2/invoicing/invoices?page_size=50".into(),
+///     rel: LinkRel::SelfLink,
+///     method: None,
+///     extra: Default::default(),
+/// };
+/// let invoices = Paginator::new(move |request| {
+///     let link = match &request.cursor {
+///         Some(Cursor::Link(next)) => next,
+///         _ => &first,
+///     };
+///     let page: Invoices = client.follow(link)?;
+///     Ok(Page::linked(page.items, &page.links))
+/// })
+/// .with_max_items(500);
+/// for invoice in invoices {
+///     println!("{}", invoice.unwrap()["id"]);
+/// }
+/// ```
+pub struct Paginator<T> {
+    fetch: Fetch<T>,
+    page_size: Option<u32>,
+    max_items: Option<usize>,
+    items: VecDeque<T>,
+    next: Option<Cursor>,
+    yielded: usize,
+    started: bool,
+    done: bool,
+}
+
+impl<T> Paginator<T> {
+    /// Walks the list whose pages `fetch` returns.
+    pub fn new<F>(fetch: F) -> Self
+    where
+        F: FnMut(&PageRequest) -> Resp<Page<T>> + Send + 'static,
+    {
+        Self {
+            fetch: Box::new(fetch),
+            page_size: None,
+            max_items: None,
+            items: VecDeque::new(),
+            next: None,
+            yielded: 0,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Asks for `page_size` items per page instead of the endpoint's default.
+    pub fn with_page_size(mut self, page_size: u32) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Stops after `max_items` items, without fetching pages beyond them. Pages are still asked
+    /// for at the configured size, and the items past `max_items` dropped.
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    // every page is asked for at the same size, as endpoints numbering their pages count them in
+    // pages of that size
+    fn fetch(&mut self) -> Resp<()> {
+        let request = PageRequest {
+            cursor: self.next.take(),
+            page_size: self.page_size,
+        };
+        self.started = true;
+        let page = (self.fetch)(&request)?;
+        // an endpoint pointing back at the same page, or answering with empty pages that all
+        // have a next one, would be walked forever
+        if page.next.is_some() && page.next == request.cursor {
+            return Err(Error::new(format!(
+                "the list returned {:?} as its next page again",
+                request.cursor
+            )));
+        }
+        if page.items.is_empty() && page.next.is_some() {
+            return Err(Error::new(format!(
+                "the list returned an empty page followed by {:?}",
+                page.next
+            )));
+        }
+        self.items.extend(page.items);
+        self.next = page.next;
+        Ok(())
+    }
+}
+
+impl<T> Iterator for Paginator<T> {
+    type Item = Resp<T>;
+
+    fn next(&mut self) -> Option<Resp<T>> {
+        loop {
+            if self.max_items.is_some_and(|max| self.yielded >= max) {
+                return None;
+            }
+            if let Some(item) = self.items.pop_front() {
+                self.yielded += 1;
+                return Some(Ok(item));
+            }
+            if self.done || (self.started && self.next.is_none()) {
+                return None;
+            }
+            if let Err(err) = self.fetch() {
+                self.done = true;
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+pub use stream::PageStream;
+
+#[cfg(feature = "stream")]
+mod stream {
+    use super::Paginator;
+    use crate::request::Resp;
+    use futures_core::Stream;
+    use std::pin::Pin;
+    use std::sync::mpsc::{sync_channel, Receiver, TryRecvError};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+
+    impl<T: Send + 'static> Paginator<T> {
+        /// Turns the paginator into a `Stream`, for use from async code. As the client is
+        /// blocking, pages are fetched on a thread of their own, at most one page ahead of the
+        /// items consumed.
+        ///
+        /// Needs the `stream` feature.
+        pub fn into_stream(self) -> PageStream<T> {
+            // the thread waits with one item in the channel while the paginator holds the rest of
+            // its page, so it only fetches a page once the one before has been handed over
+            let (sender, receiver) = sync_channel(1);
+            let waker: Arc<Mutex<Option<Waker>>> = Arc::default();
+            let wake = waker.clone();
+            let notify = move || {
+                if let Some(waker) = wake.lock().unwrap().take() {
+                    waker.wake();
+                }
+            };
+            std::thread::spawn(move || {
+                for item in self {
+                    if sender.send(item).is_err() {
+                        return;
+                    }
+                    notify();
+                }
+                drop(sender);
+                notify();
+            });
+            PageStream { receiver, waker }
+        }
+    }
+
+    /// The items of a `Paginator` as a `Stream`, returned by `Paginator::into_stream`.
+    pub struct PageStream<T> {
+        receiver: Receiver<Resp<T>>,
+        waker: Arc<Mutex<Option<Waker>>>,
+    }
+
+    impl<T> Stream for PageStream<T> {
+        type Item = Resp<T>;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Resp<T>>> {
+            // the waker is registered before looking again, so that an item sent in between
+            // is not missed
+            for attempt in 0..2 {
+                match self.receiver.try_recv() {
+                    Ok(item) => return Poll::Ready(Some(item)),
+                    Err(TryRecvError::Disconnected) => return Poll::Ready(None),
+                    Err(TryRecvError::Empty) if attempt == 0 => {
+                        *self.waker.lock().unwrap() = Some(cx.waker().clone());
+                    }
+                    Err(TryRecvError::Empty) => {}
+                }
+            }
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    // the numbers 1 to 7 in numbered pages of the size asked for, 3 by default, recording the
+    // requests made
+    fn numbers(requests: Arc<Mutex<Vec<PageRequest>>>) -> Paginator<u32> {
+        Paginator::new(move |request| {
+            requests.lock().unwrap().push(request.clone());
+            let page = match request.cursor {
+                None => 1,
+                Some(Cursor::Page(page)) => page,
+                _ => unreachable!(),
+            };
+            let size = request.page_size.unwrap_or(3);
+            let items = ((page - 1) * size + 1..=(page * size).min(7)).collect();
+            Ok(Page::numbered(items, page, (7 + size - 1) / size))
+        })
+    }
+
+    fn page_sizes(requests: &Mutex<Vec<PageRequest>>) -> Vec<Option<u32>> {
+        requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| r.page_size)
+            .collect()
+    }
+
+    #[test]
+    fn test_paginator() {
+        let requests = Arc::default();
+        let all: Vec<u32> = numbers(Arc::clone(&requests)).map(Result::unwrap).collect();
+        assert_eq!(vec![1, 2, 3, 4, 5, 6, 7], all);
+        assert_eq!(3, requests.lock().unwrap().len());
+
+        let requests = Arc::default();
+        let some: Vec<u32> = numbers(Arc::clone(&requests))
+            .with_page_size(3)
+            .with_max_items(4)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(vec![1, 2, 3, 4], some);
+        assert_eq!(vec![Some(3), Some(3)], page_sizes(&requests));
+
+        // a smaller last page would start at the wrong offset
+        let requests = Arc::default();
+        let some: Vec<u32> = numbers(Arc::clone(&requests))
+            .with_page_size(2)
+            .with_max_items(5)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(vec![1, 2, 3, 4, 5], some);
+        assert_eq!(vec![Some(2); 3], page_sizes(&requests));
+    }
+
+    #[test]
+    fn test_empty_page_with_a_next_one() {
+        let mut pages = Paginator::new(|request: &PageRequest| match &request.cursor {
+            None => Ok(Page::<u32>::new(vec![], Some(Cursor::StartId("2".into())))),
+            Some(cursor) => panic!("fetched {:?}", cursor),
+        });
+        assert!(pages.next().unwrap().is_err());
+        assert!(pages.next().is_none());
+    }
+
+    #[test]
+    fn test_error_ends_the_iteration() {
+        let mut pages = Paginator::new(|request: &PageRequest| match request.cursor {
+            None => Ok(Page::new(vec![1], Some(Cursor::StartId("2".into())))),
+            Some(_) => Err(Error::new("unavailable".into())),
+        });
+        assert_eq!(1, pages.next().unwrap().unwrap());
+        assert!(pages.next().unwrap().is_err());
+        assert!(pages.next().is_none());
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn test_stream() {
+        use futures_core::Stream;
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct Unpark(std::thread::Thread);
+
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let mut stream = numbers(Arc::default()).into_stream();
+        let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut all = Vec::new();
+        loop {
+            match std::pin::Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(Some(item)) => all.push(item.unwrap()),
+                Poll::Ready(None) => break,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+        assert_eq!(vec![1, 2, 3, 4, 5, 6, 7], all);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::Resp;
+use crate::types::{
+    MerchantIntegration, PartnerReferral, PartnerReferralDetails, PartnerReferralLinks,
+};
+
+/// Creates a partner referral for a seller. Send the seller to the `action_url` link of the
+/// response to have them sign up and grant the partner the requested permissions.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, partner_referral, Client};
+/// use paypal::{LegalConsent, PartnerReferral};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let referral = PartnerReferral {
+///     tracking_id: Some("seller-42".to_string()),
+///     products: vec!["EXPRESS_CHECKOUT".to_string()],
+///     legal_consents: vec![LegalConsent {
+///         _type: "SHARE_DATA_CONSENT".to_string(),
+///         granted: true,
+///         ..Default::default()
+///     }],
+///     ..Default::default()
+/// };
+/// let links = partner_referral::create(&Client::new(&token.access_token), &referral).unwrap();
+/// ```
+pub fn create(client: &Client, referral: &PartnerReferral) -> Resp<PartnerReferralLinks> {
+    crate::request::post_json(
+        client,
+        &client.endpoint("/v2/customer/partner-referrals"),
+        referral,
+    )
+}
+
+/// Shows the data of a partner referral, by ID.
+pub fn get(client: &Client, referral_id: &str) -> Resp<PartnerReferralDetails> {
+    crate::request::get(
+        client,
+        &client.endpoint(&format!("/v2/customer/partner-referrals/{}", referral_id)),
+    )
+}
+
+/// Shows the integration status of a seller connected to the partner, for example whether it can
+/// receive payments yet.
+pub fn merchant_integration(
+    client: &Client,
+    partner_id: &str,
+    merchant_id: &str,
+) -> Resp<MerchantIntegration> {
+    crate::request::get(
+        client,
+        &client.endpoint(&format!(
+            "/v1/customer/partners/{}/merchant-integrations/{}",
+            partner_id, merchant_id
+        )),
+    )
+}
+
+/// Looks up a seller connected to the partner by the `tracking_id` given in its referral. Only
+/// the identifying fields of the result are filled in; use `merchant_integration` with the
+/// returned `merchant_id` for the full status.
+pub fn find_merchant(
+    client: &Client,
+    partner_id: &str,
+    tracking_id: &str,
+) -> Resp<MerchantIntegration> {
+    let query = serde_urlencoded::to_string([("tracking_id", tracking_id)])
+        .map_err(|err| Error::new(err.to_string()))?;
+    crate::request::get(
+        client,
+        &client.endpoint(&format!(
+            "/v1/customer/partners/{}/merchant-integrations?{}",
+            partner_id, query
+        )),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpRequest, HttpResponse, Transport};
+    use std::sync::{Arc, Mutex};
+
+    // answers every lookup with the same seller and remembers the urls it was asked for
+    struct Seller(Arc<Mutex<Vec<String>>>);
+
+    impl Transport for Seller {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+            self.0.lock().unwrap().push(request.url.clone());
+            Ok(HttpResponse {
+                status: 200,
+                headers: Default::default(),
+                body: br#"{"merchant_id": "SELLERID42"}"#.to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_find_merchant_encodes_the_tracking_id() {
+        let urls = Arc::new(Mutex::new(Vec::new()));
+        let client = Client::new("token")
+            .with_base_url("https://example.com")
+            .with_transport(Seller(urls.clone()));
+        let merchant = find_merchant(&client, "PARTNER", "seller 42&products=x").unwrap();
+        assert_eq!("SELLERID42", merchant.merchant_id);
+        assert_eq!(
+            vec![
+                "https://example.com/v1/customer/partners/PARTNER/merchant-integrations\
+                 ?tracking_id=seller+42%26products%3Dx"
+                    .to_string()
+            ],
+            *urls.lock().unwrap()
+        );
+    }
+}
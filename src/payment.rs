@@ -1,71 +1,160 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::pagination::{Cursor, Page, Paginator};
 use crate::request::Resp;
-use crate::types::Payment;
 use crate::types::{
-    ListPaymentResponse, Payer, PaymentIntent, PaymentMethod, RedirectUrls, RequestNewPayment,
-    Transaction,
+    ListPaymentResponse, PaymentIntent, PaymentMethod, RequestNewPayment, Transaction,
 };
+use crate::types::{PatchOp, Payment};
 use std::collections::HashMap;
 
 /// Use this endpoint to create a new payment.
 /// You can obtain a new bearer token using the `get_token` function provided.
 /// ```rust,no_run
-/// use paypal::{get_token, payment};
+/// use paypal::{get_token, payment, Client};
 /// use paypal::{PaymentMethod, PaymentIntent, Transaction, TransactionAmount};
 ///
 /// let token = get_token("my_id", "my_secret").unwrap();
+/// let client = Client::new(&token.access_token);
 /// let amount = TransactionAmount {
 ///     currency: "USD".to_string(),
-///     total: "100.00".to_string()
+///     total: "100.00".to_string(),
+///     ..Default::default()
 /// };
 /// let new_payment = payment::new(
-///     &token.access_token,
+///     &client,
 ///     "mysite.com/whooyoupaid",
 ///     "mysite.com/nooyoufailed",
 ///     PaymentMethod::Paypal,
 ///     PaymentIntent::Sale,
-///     vec![Transaction { amount }],
+///     vec![Transaction { amount, ..Default::default() }],
 /// ).unwrap();
 /// ```
 pub fn new(
-    bearer: &str,
+    client: &Client,
     return_url: &str,
     cancel_url: &str,
     method: PaymentMethod,
     intent: PaymentIntent,
     transactions: Vec<Transaction>,
 ) -> Resp<Payment> {
-    let ep = crate::_make_endpoint("/v1/payments/payment");
-    let mut headers = HashMap::new();
-    headers.insert("Authorization".into(), format!("Bearer {}", bearer));
-    let body = RequestNewPayment {
-        redirect_urls: RedirectUrls {
-            return_url: return_url.into(),
-            cancel_url: cancel_url.into(),
-        },
-        payer: Payer {
-            payment_method: method,
-            funding_instruments: None,
-            status: None,
-        },
-        intent,
-        transactions,
-    };
-    crate::request::post_json(ep.as_str(), &mut headers, &body)
+    create(
+        client,
+        &RequestNewPayment::new(return_url, cancel_url, method, intent, transactions),
+    )
+}
+
+/// Creates a new payment from a full request body. Unlike `new`, this can also set fields such
+/// as the web experience profile to show the payer.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, payment, Client, RequestNewPayment};
+/// use paypal::{PaymentMethod, PaymentIntent, Transaction, TransactionAmount};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let client = Client::new(&token.access_token);
+/// let amount = TransactionAmount {
+///     currency: "USD".to_string(),
+///     total: "100.00".to_string(),
+///     ..Default::default()
+/// };
+/// let body = RequestNewPayment::new(
+///     "mysite.com/whooyoupaid",
+///     "mysite.com/nooyoufailed",
+///     PaymentMethod::Paypal,
+///     PaymentIntent::Sale,
+///     vec![Transaction { amount, ..Default::default() }],
+/// )
+/// .with_experience_profile("XP-CP6S-W9DY-96H8-MVN2");
+/// let new_payment = payment::create(&client, &body).unwrap();
+/// ```
+pub fn create(client: &Client, body: &RequestNewPayment) -> Resp<Payment> {
+    crate::request::post_json(client, &client.endpoint("/v1/payments/payment"), body)
 }
 
 /// Returns a list of all transactions made using the account that corresponds to the
-/// provided bearer token.
+/// client's bearer token.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, payment, Client};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let list = payment::list(&Client::new(&token.access_token)).unwrap();
+/// ```
+pub fn list(client: &Client) -> Resp<ListPaymentResponse> {
+    crate::request::get(client, &client.endpoint("/v1/payments/payment"))
+}
+
+/// Iterates over all payments, fetching them a page at a time.
 ///
 /// ```rust,no_run
+/// use paypal::{get_token, payment, Client};
+///
 /// let token = get_token("my_id", "my_secret").unwrap();
-/// let list = payment::list(&token).unwrap();
+/// let client = Client::new(&token.access_token);
+/// for payment in payment::list_all(&client).with_page_size(20).with_max_items(100) {
+///     println!("{}", payment.unwrap().id);
+/// }
 /// ```
-pub fn list(bearer: &str) -> Resp<ListPaymentResponse> {
-    let mut headers = HashMap::new();
-    headers.insert("Authorization".into(), format!("Bearer {}", bearer));
+pub fn list_all(client: &Client) -> Paginator<Payment> {
+    let client = client.clone();
+    Paginator::new(move |request| {
+        let mut query = Vec::new();
+        if let Some(count) = request.page_size {
+            query.push(("count", count.to_string()));
+        }
+        if let Some(Cursor::StartId(start_id)) = &request.cursor {
+            query.push(("start_id", start_id.clone()));
+        }
+        let mut ep = client.endpoint("/v1/payments/payment");
+        if !query.is_empty() {
+            ep.push('?');
+            ep.push_str(
+                &serde_urlencoded::to_string(&query).map_err(|err| Error::new(err.to_string()))?,
+            );
+        }
+        let list: ListPaymentResponse = crate::request::get(&client, &ep)?;
+        Ok(Page::new(list.payments, list.next_id.map(Cursor::StartId)))
+    })
+}
+
+/// Shows the details of the payment with the given ID.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, payment, Client};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let payment = payment::get(&Client::new(&token.access_token), "PAYID-LZ7ZYRA").unwrap();
+/// ```
+pub fn get(client: &Client, payment_id: &str) -> Resp<Payment> {
     crate::request::get(
-        crate::_make_endpoint("/v1/payments/payment").as_str(),
-        &headers,
+        client,
+        &client.endpoint(&format!("/v1/payments/payment/{}", payment_id)),
+    )
+}
+
+/// Updates a payment that was created but not yet executed, for example to change its amount or
+/// shipping address.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, payment, Client, PatchOp, PatchPath, TransactionAmount};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let client = Client::new(&token.access_token);
+/// let amount = TransactionAmount {
+///     currency: "USD".to_string(),
+///     total: "25.00".to_string(),
+///     ..Default::default()
+/// };
+/// let path = PatchPath::root().field("transactions").index(0).field("amount");
+/// let ops = [PatchOp::replace(path, &amount).unwrap()];
+/// let payment = payment::update(&client, "PAYID-LZ7ZYRA", &ops).unwrap();
+/// ```
+pub fn update(client: &Client, payment_id: &str, ops: &[PatchOp]) -> Resp<Payment> {
+    crate::request::patch_json(
+        client,
+        &client.endpoint(&format!("/v1/payments/payment/{}", payment_id)),
+        ops,
     )
 }
 
@@ -73,34 +162,35 @@ pub fn list(bearer: &str) -> Resp<ListPaymentResponse> {
 /// has been created and _approved_ by the customer.
 ///
 /// ```rust,no_run
-/// use paypal::{get_token, payment};
+/// use paypal::{get_token, payment, Client};
 /// use paypal::{PaymentMethod, PaymentIntent, Transaction, TransactionAmount};
+/// # fn function_that_sends_user_to_webpage(_: &paypal::Payment) -> String { unimplemented!() }
 ///
 /// let token = get_token("my_id", "my_secret").unwrap();
+/// let client = Client::new(&token.access_token);
 /// let amount = TransactionAmount {
 ///     currency: "USD".to_string(),
-///     total: "100.00".to_string()
+///     total: "100.00".to_string(),
+///     ..Default::default()
 /// };
 /// let new_payment = payment::new(
-///     &token.access_token,
+///     &client,
 ///     "mysite.com/whooyoupaid",
 ///     "mysite.com/nooyoufailed",
 ///     PaymentMethod::Paypal,
 ///     PaymentIntent::Sale,
-///     vec![Transaction { amount }],
+///     vec![Transaction { amount, ..Default::default() }],
 /// ).unwrap();
 /// // Have the user approve the payment here, using the webpage in payment.links, for example:
 /// let payer_id = function_that_sends_user_to_webpage(&new_payment);
-/// let finalized_payment = payment::execute(&token, &new_payment.id, &payer_id).unwrap();
+/// let finalized_payment = payment::execute(&client, &new_payment.id, &payer_id).unwrap();
 /// ```
-pub fn execute(bearer: &str, payment_id: &str, payer_id: &str) -> Resp<Payment> {
-    let mut headers = HashMap::new();
-    let mut body = headers.clone();
-    headers.insert("Authorization".into(), format!("Bearer {}", bearer));
+pub fn execute(client: &Client, payment_id: &str, payer_id: &str) -> Resp<Payment> {
+    let mut body = HashMap::<String, String>::new();
     body.insert("payer_id".into(), payer_id.into());
     crate::request::post_json(
-        &crate::_make_endpoint(&format!("/v1/payments/payment/{}/execute", payment_id)).as_str(),
-        &mut headers,
+        client,
+        &client.endpoint(&format!("/v1/payments/payment/{}/execute", payment_id)),
         &body,
     )
 }
@@ -108,41 +198,59 @@ pub fn execute(bearer: &str, payment_id: &str, payer_id: &str) -> Resp<Payment>
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cassette::Cassette;
     use lazy_static::lazy_static;
-    use std::io::{self, BufRead};
 
     lazy_static! {
         static ref CLIENT_ID: String = {
             dotenv::dotenv().ok();
-            std::env::var("CLIENT_ID").expect("`CLIENT_ID` env parameter to be set")
+            // only used when recording the cassettes, replaying needs no credentials
+            std::env::var("CLIENT_ID").unwrap_or_default()
         };
         static ref SECRET: String = {
             dotenv::dotenv().ok();
-            std::env::var("SECRET").expect("`SECRET` env parameter to be set")
+            std::env::var("SECRET").unwrap_or_default()
         };
     }
 
     #[test]
     fn test_new() {
-        let token = crate::get_token(&CLIENT_ID, &SECRET).unwrap(); // alos checked by other test
-        let amount = crate::types::TransactionAmount {
-            currency: "USD".to_string(),
-            total: "100.00".to_string(),
-        };
-        new(
-            &token.access_token,
-            "mysite.com/whooyoupaid",
-            "mysite.com/nooyoufailed",
-            PaymentMethod::Paypal,
-            PaymentIntent::Sale,
-            vec![Transaction { amount }],
-        )
+        Cassette::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/payment_new.json"
+        ))
+        .run(|| {
+            let token = crate::get_token(&CLIENT_ID, &SECRET).unwrap(); // alos checked by other test
+            let amount = crate::types::TransactionAmount {
+                currency: "USD".to_string(),
+                total: "100.00".to_string(),
+                ..Default::default()
+            };
+            new(
+                &Client::new(&token.access_token),
+                "mysite.com/whooyoupaid",
+                "mysite.com/nooyoufailed",
+                PaymentMethod::Paypal,
+                PaymentIntent::Sale,
+                vec![Transaction {
+                    amount,
+                    ..Default::default()
+                }],
+            )
+        })
         .unwrap();
     }
 
     #[test]
     fn test_list() {
-        let token = crate::get_token(&CLIENT_ID, &SECRET).unwrap();
-        let _list = list(&token.access_token).unwrap();
+        Cassette::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/payment_list.json"
+        ))
+        .run(|| {
+            let token = crate::get_token(&CLIENT_ID, &SECRET).unwrap();
+            list(&Client::new(&token.access_token))
+        })
+        .unwrap();
     }
 }
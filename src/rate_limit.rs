@@ -0,0 +1,259 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A client-side rate limiter: token buckets that delay calls, rather than failing them, once
+/// a client goes faster than allowed. One bucket covers every call, and more can be added for
+/// endpoint families. Install one with `Client::with_rate_limiter`. Clones share their buckets,
+/// so one limiter can pace several clients.
+///
+/// A client with a rate limiter also retries calls PayPal answers with `429 Too Many Requests`,
+/// pausing the buckets of that endpoint for as long as PayPal's `Retry-After` header asks, up to
+/// a minute, or backing off exponentially when it does not say.
+///
+/// ```rust
+/// use paypal::rate_limit::RateLimiter;
+/// use paypal::Client;
+///
+/// // 20 calls a second overall, in bursts of up to 40, but only 2 refunds a second
+/// let limiter = RateLimiter::new(20.0, 40).with_family("/v1/payments/sale/{id}/refund", 2.0, 2);
+/// let client = Client::new("token").with_rate_limiter(limiter);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    global: Arc<Mutex<Bucket>>,
+    families: Vec<(String, Arc<Mutex<Bucket>>)>,
+    max_retries: u32,
+    max_delay: Duration,
+}
+
+impl RateLimiter {
+    /// Allows `per_second` calls a second on average, and up to `burst` calls at once.
+    ///
+    /// # Panics
+    ///
+    /// When `per_second` is not a positive, finite number.
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        Self {
+            global: Arc::new(Mutex::new(Bucket::new(per_second, burst))),
+            families: Vec::new(),
+            max_retries: 3,
+            max_delay: Duration::from_secs(60),
+        }
+    }
+
+    /// Additionally limits the calls to endpoints whose path starts with `prefix`, where `{id}`
+    /// stands for any id, for example `/v1/reporting/transactions` or
+    /// `/v1/payments/sale/{id}/refund`.
+    ///
+    /// # Panics
+    ///
+    /// When `per_second` is not a positive, finite number.
+    pub fn with_family(mut self, prefix: &str, per_second: f64, burst: u32) -> Self {
+        let bucket = Arc::new(Mutex::new(Bucket::new(per_second, burst)));
+        self.families.push((prefix.into(), bucket));
+        self
+    }
+
+    /// How often a call answered with `429 Too Many Requests` is retried. Defaults to 3.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// The longest a call answered with `429 Too Many Requests` waits before it is retried,
+    /// whatever PayPal's `Retry-After` header asks for. Defaults to a minute.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub(crate) fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    pub(crate) fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    // waits until a call to `endpoint` is allowed, returning how long that took
+    pub(crate) fn acquire(&self, endpoint: &str) -> Duration {
+        let started = Instant::now();
+        for bucket in self.buckets(endpoint) {
+            loop {
+                let wait = bucket.lock().unwrap().take();
+                match wait {
+                    Some(wait) => std::thread::sleep(wait),
+                    None => break,
+                }
+            }
+        }
+        started.elapsed()
+    }
+
+    // holds back every call to `endpoint` for `delay`, after PayPal answered one with a 429
+    pub(crate) fn pause(&self, endpoint: &str, delay: Duration) {
+        for bucket in self.buckets(endpoint) {
+            bucket.lock().unwrap().pause(delay);
+        }
+    }
+
+    fn buckets<'a>(&'a self, endpoint: &'a str) -> impl Iterator<Item = &'a Arc<Mutex<Bucket>>> {
+        self.families
+            .iter()
+            .filter(move |(prefix, _)| matches_prefix(prefix, endpoint))
+            .map(|(_, bucket)| bucket)
+            .chain(std::iter::once(&self.global))
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    per_second: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(per_second: f64, burst: u32) -> Self {
+        assert!(
+            per_second.is_finite() && per_second > 0.0,
+            "a rate limit must allow a positive, finite number of calls a second, not {}",
+            per_second
+        );
+        let burst = f64::from(burst.max(1));
+        Self {
+            per_second,
+            burst,
+            tokens: burst,
+            updated: Instant::now(),
+            paused_until: None,
+        }
+    }
+
+    // takes a token, or tells how long to wait before trying again
+    fn take(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        if let Some(until) = self.paused_until {
+            if until > now {
+                return Some(until - now);
+            }
+            self.paused_until = None;
+        }
+        let refill = now.duration_since(self.updated).as_secs_f64() * self.per_second;
+        self.tokens = (self.tokens + refill).min(self.burst);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.per_second,
+            ))
+        }
+    }
+
+    fn pause(&mut self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let until = self.paused_until.map_or(until, |paused| paused.max(until));
+        // a single call may go once the pause is over, the bucket refills from then on
+        self.paused_until = Some(until);
+        self.updated = until;
+        self.tokens = 1.0;
+    }
+}
+
+// endpoints come with their ids replaced by `{id}`, so a prefix matches segment by segment
+fn matches_prefix(prefix: &str, endpoint: &str) -> bool {
+    let prefix: Vec<_> = prefix.trim_end_matches('/').split('/').collect();
+    let endpoint: Vec<_> = endpoint.split('/').collect();
+    endpoint.len() >= prefix.len() && prefix.iter().zip(&endpoint).all(|(p, e)| p == e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_prefix() {
+        assert!(matches_prefix(
+            "/v1/payments/sale/{id}/refund",
+            "/v1/payments/sale/{id}/refund"
+        ));
+        assert!(matches_prefix("/v1/payments/", "/v1/payments/payment/{id}"));
+        assert!(!matches_prefix("/v1/payments/sale", "/v1/payments/saleX"));
+        assert!(!matches_prefix(
+            "/v1/payments/payment/{id}",
+            "/v1/payments/payment"
+        ));
+    }
+
+    #[test]
+    fn test_bucket() {
+        let mut bucket = Bucket::new(10.0, 2);
+        assert_eq!(None, bucket.take());
+        assert_eq!(None, bucket.take());
+        let wait = bucket.take().unwrap();
+        assert!(wait > Duration::from_millis(50) && wait <= Duration::from_millis(100));
+
+        bucket.pause(Duration::from_secs(5));
+        assert!(bucket.take().unwrap() > Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_rejects_invalid_rates() {
+        for per_second in &[0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let per_second = *per_second;
+            assert!(std::panic::catch_unwind(|| RateLimiter::new(per_second, 1)).is_err());
+            assert!(std::panic::catch_unwind(|| {
+                RateLimiter::new(1.0, 1).with_family("/v1/payments/", per_second, 1)
+            })
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_retries_after_429() {
+        use crate::transport::{HttpRequest, HttpResponse, Transport};
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        // rejects the first call, then answers with an empty list
+        struct Busy(AtomicU32);
+
+        impl Transport for Busy {
+            fn send(&self, _: &HttpRequest) -> Result<HttpResponse, crate::error::Error> {
+                if self.0.fetch_add(1, Ordering::SeqCst) == 0 {
+                    let mut headers = std::collections::HashMap::new();
+                    headers.insert("retry-after".to_string(), "86400".to_string());
+                    return Ok(HttpResponse {
+                        status: 429,
+                        headers,
+                        body: Vec::new(),
+                    });
+                }
+                Ok(HttpResponse {
+                    status: 200,
+                    headers: Default::default(),
+                    body: br#"{"payments": [], "count": 0}"#.to_vec(),
+                })
+            }
+        }
+
+        let client = crate::Client::new("token")
+            .with_transport(Busy(AtomicU32::new(0)))
+            .with_rate_limiter(
+                RateLimiter::new(100.0, 10).with_max_delay(Duration::from_millis(10)),
+            );
+        let started = Instant::now();
+        assert_eq!(0, crate::payment::list(&client).unwrap().count);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let client = client.with_rate_limiter(RateLimiter::new(100.0, 10).with_max_retries(0));
+        let client = client.with_transport(Busy(AtomicU32::new(0)));
+        assert_eq!(
+            Some(429),
+            crate::payment::list(&client).unwrap_err().status()
+        );
+    }
+}
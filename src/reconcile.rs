@@ -0,0 +1,713 @@
+use crate::client::Client;
+use crate::request::Resp;
+use crate::types::{Capture, CaptureRefund, Payment, Refund, Sale, State};
+use crate::types::{TransactionDetails, TransactionSearch, TransactionStatus};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// A payment, sale, capture or refund as recorded in the local order database.
+#[derive(Clone, Debug)]
+pub struct Record {
+    /// The ID PayPal gave the payment, sale, capture or refund.
+    pub id: String,
+    /// What the ID is the ID of, so that `fetch_and_reconcile` knows where to look it up.
+    pub kind: Kind,
+    /// The amount charged, for example "12.34". Transaction search reports money leaving the
+    /// account, such as refunds, as negative amounts, while looking a refund up by its ID gives
+    /// a positive one, so the amounts of refunds compare the same whatever their sign.
+    pub amount: String,
+    /// The ISO 4217 currency code, for example "USD".
+    pub currency: String,
+    /// The state the payment should be in at PayPal. See `Remote` for how the statuses of
+    /// captures, refunds and transactions are named.
+    pub expected_state: State,
+}
+
+/// What the ID of a `Record` identifies.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Kind {
+    /// A v1 payment.
+    #[default]
+    Payment,
+    /// A sale of a v1 payment.
+    Sale,
+    /// A refund of a v1 sale.
+    SaleRefund,
+    /// A payment captured for a v2 order.
+    Capture,
+    /// A refund of a v2 capture.
+    CaptureRefund,
+}
+
+/// PayPal's side of a comparison: a payment, sale, capture, refund or found transaction, with
+/// its amount and state in the terms of a `Record`. Statuses become the `State` of the same name,
+/// such as `COMPLETED` becoming `State::Completed`, and `State::Unknown` with the lowercase name
+/// when there is none, such as `State::Unknown("declined")`. The transaction search statuses `S`,
+/// `P`, `D` and `V` become `completed`, `pending`, `denied` and `reversed`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Remote {
+    /// The ID PayPal gave it.
+    pub id: String,
+    /// The amount, for example "12.34", unless PayPal did not send one or a payment's
+    /// transactions are in several currencies.
+    pub amount: Option<String>,
+    /// The ISO 4217 currency code of the amount.
+    pub currency: Option<String>,
+    /// The state at PayPal.
+    pub state: Option<State>,
+}
+
+impl From<&Payment> for Remote {
+    fn from(payment: &Payment) -> Self {
+        let total = total(payment);
+        Self {
+            id: payment.id.clone(),
+            amount: total.map(|(sum, _)| format_units(sum)),
+            currency: total.map(|(_, currency)| currency.to_string()),
+            state: payment.state.clone(),
+        }
+    }
+}
+
+impl From<&Sale> for Remote {
+    fn from(sale: &Sale) -> Self {
+        Self {
+            id: sale.id.clone(),
+            amount: Some(sale.amount.total.clone()),
+            currency: Some(sale.amount.currency.clone()),
+            state: sale.state.clone(),
+        }
+    }
+}
+
+impl From<&Refund> for Remote {
+    fn from(refund: &Refund) -> Self {
+        Self {
+            id: refund.id.clone(),
+            amount: refund.amount.as_ref().map(|amount| amount.total.clone()),
+            currency: refund.amount.as_ref().map(|amount| amount.currency.clone()),
+            state: refund.state.clone(),
+        }
+    }
+}
+
+impl From<&Capture> for Remote {
+    fn from(capture: &Capture) -> Self {
+        Self {
+            id: capture.id.clone(),
+            amount: capture.amount.as_ref().map(|amount| amount.value.clone()),
+            currency: capture
+                .amount
+                .as_ref()
+                .map(|amount| amount.currency_code.clone()),
+            state: capture.status.as_ref().map(|status| state(status.as_str())),
+        }
+    }
+}
+
+impl From<&CaptureRefund> for Remote {
+    fn from(refund: &CaptureRefund) -> Self {
+        Self {
+            id: refund.id.clone(),
+            amount: refund.amount.as_ref().map(|amount| amount.value.clone()),
+            currency: refund
+                .amount
+                .as_ref()
+                .map(|amount| amount.currency_code.clone()),
+            state: refund.status.as_ref().map(|status| state(status.as_str())),
+        }
+    }
+}
+
+impl From<&TransactionDetails> for Remote {
+    fn from(details: &TransactionDetails) -> Self {
+        let info = &details.transaction_info;
+        let amount = info.transaction_amount.as_ref();
+        Self {
+            id: info.transaction_id.clone(),
+            amount: amount.map(|amount| amount.value.clone()),
+            currency: amount.map(|amount| amount.currency_code.clone()),
+            state: info.transaction_status.as_ref().map(|status| match status {
+                TransactionStatus::Success => State::Completed,
+                TransactionStatus::Pending => State::Pending,
+                TransactionStatus::Denied => state("denied"),
+                TransactionStatus::Reversed => state("reversed"),
+                TransactionStatus::Unknown(status) => state(status),
+            }),
+        }
+    }
+}
+
+impl From<&Remote> for Remote {
+    fn from(remote: &Remote) -> Self {
+        remote.clone()
+    }
+}
+
+/// How a payment compares between the local records and PayPal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Amount, currency and state agree.
+    Matched,
+    /// Recorded locally, but unknown to PayPal.
+    Missing,
+    /// PayPal has a different amount or currency than the record.
+    AmountMismatch,
+    /// The amounts agree, but the payment is not in the expected state.
+    UnexpectedState,
+    /// Known to PayPal, but not recorded locally.
+    Unrecorded,
+}
+
+/// One line of a `Report`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Entry {
+    /// The ID of the payment.
+    pub id: String,
+    /// How the payment compares.
+    pub status: Status,
+    /// The amount and currency of the record, if there is one, for example "12.34 USD".
+    pub local_amount: Option<String>,
+    /// The amount and currency at PayPal, if PayPal knows the payment.
+    pub paypal_amount: Option<String>,
+    /// The state of the record, if there is one.
+    pub expected_state: Option<State>,
+    /// The state at PayPal, if PayPal knows the payment.
+    pub paypal_state: Option<State>,
+}
+
+/// The outcome of comparing local records with PayPal, one entry per record, in their order,
+/// followed by one per payment only PayPal knows.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Report {
+    /// The compared payments.
+    pub entries: Vec<Entry>,
+}
+
+impl Report {
+    /// The entries with the given status.
+    pub fn with_status(&self, status: Status) -> impl Iterator<Item = &Entry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.status == status)
+    }
+
+    /// Whether every entry matched.
+    pub fn is_clean(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|entry| entry.status == Status::Matched)
+    }
+
+    /// The report as CSV, with a header line.
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("id,status,local_amount,paypal_amount,expected_state,paypal_state\n");
+        for entry in &self.entries {
+            let status = serde_json::to_value(entry.status).unwrap_or_default();
+            let state = |state: &Option<State>| state.as_ref().map(State::to_string);
+            let fields = [
+                Some(entry.id.clone()),
+                status.as_str().map(String::from),
+                entry.local_amount.clone(),
+                entry.paypal_amount.clone(),
+                state(&entry.expected_state),
+                state(&entry.paypal_state),
+            ];
+            let line: Vec<String> = fields
+                .iter()
+                .map(|field| csv_field(field.as_deref().unwrap_or_default()))
+                .collect();
+            writeln!(csv, "{}", line.join(",")).unwrap();
+        }
+        csv
+    }
+
+    /// The report as a JSON array of entries.
+    pub fn to_json(&self) -> Resp<String> {
+        Ok(serde_json::to_string_pretty(&self.entries)?)
+    }
+}
+
+/// Compares `records` with what PayPal has: payments, for example all payments of the day from
+/// `payment::list_all`, or sales, captures, refunds or found transactions. To compare with
+/// several kinds at once, convert them to `Remote`s first.
+///
+/// Records sharing an ID are each compared with the same payment.
+pub fn reconcile<'a, T>(records: &[Record], found: &'a [T]) -> Report
+where
+    &'a T: Into<Remote>,
+{
+    let found: Vec<Remote> = found.iter().map(Into::into).collect();
+    let remote: HashMap<&str, &Remote> = found
+        .iter()
+        .map(|remote| (remote.id.as_str(), remote))
+        .collect();
+    let mut seen = HashSet::new();
+    let mut entries: Vec<Entry> = records
+        .iter()
+        .map(|record| {
+            seen.insert(record.id.as_str());
+            compare(record, remote.get(record.id.as_str()).copied())
+        })
+        .collect();
+    // in the order PayPal listed them, once each
+    for remote in &found {
+        if seen.insert(remote.id.as_str()) {
+            entries.push(Entry {
+                id: remote.id.clone(),
+                status: Status::Unrecorded,
+                local_amount: None,
+                paypal_amount: paypal_amount(remote),
+                expected_state: None,
+                paypal_state: remote.state.clone(),
+            });
+        }
+    }
+    Report { entries }
+}
+
+/// Looks up every record at PayPal by its ID, where its `kind` says, and compares them. IDs PayPal
+/// answers `404 Not Found` for are reported as missing.
+///
+/// ```rust,no_run
+/// use paypal::reconcile::{self, Kind, Record};
+/// use paypal::{Client, State};
+///
+/// let client = Client::new("token");
+/// let records = vec![Record {
+///     id: "PAYID-LWZ7Q6A3SE48217XB7361725".to_string(),
+///     kind: Kind::Payment,
+///     amount: "100.00".to_string(),
+///     currency: "USD".to_string(),
+///     expected_state: State::Approved,
+/// }];
+/// let report = reconcile::fetch_and_reconcile(&client, &records).unwrap();
+/// std::fs::write("reconciliation.csv", report.to_csv()).unwrap();
+/// ```
+pub fn fetch_and_reconcile(client: &Client, records: &[Record]) -> Resp<Report> {
+    let mut found = Vec::new();
+    for record in records {
+        let id = record.id.as_str();
+        let fetched = match record.kind {
+            Kind::Payment => crate::payment::get(client, id).map(|found| Remote::from(&found)),
+            Kind::Sale => crate::sale::get(client, id).map(|found| Remote::from(&found)),
+            Kind::SaleRefund => {
+                crate::sale::get_refund(client, id).map(|found| Remote::from(&found))
+            }
+            Kind::Capture => crate::capture::get(client, id).map(|found| Remote::from(&found)),
+            Kind::CaptureRefund => {
+                crate::capture::get_refund(client, id).map(|found| Remote::from(&found))
+            }
+        };
+        match fetched {
+            Ok(remote) => found.push(remote),
+            Err(err) if err.status() == Some(404) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(reconcile(records, &found))
+}
+
+/// Compares `records` with every transaction `search` finds, such as a day's sales, captures and
+/// refunds. Transactions that are not recorded are reported as unrecorded.
+///
+/// ```rust,no_run
+/// use chrono::{Duration, Utc};
+/// use paypal::reconcile::{self, Record};
+/// use paypal::{Client, TransactionSearch};
+///
+/// let client = Client::new("token");
+/// # let records: Vec<Record> = Vec::new();
+/// let search = TransactionSearch::new(Utc::now() - Duration::days(1), Utc::now());
+/// let report = reconcile::search_and_reconcile(&client, &records, &search).unwrap();
+/// ```
+pub fn search_and_reconcile(
+    client: &Client,
+    records: &[Record],
+    search: &TransactionSearch,
+) -> Resp<Report> {
+    let found = crate::transaction::search(client, search)
+        .with_page_size(500)
+        .collect::<Resp<Vec<_>>>()?;
+    Ok(reconcile(records, &found))
+}
+
+fn compare(record: &Record, remote: Option<&Remote>) -> Entry {
+    let local_amount = Some(format!("{} {}", record.amount, record.currency));
+    let remote = match remote {
+        Some(remote) => remote,
+        None => {
+            return Entry {
+                id: record.id.clone(),
+                status: Status::Missing,
+                local_amount,
+                paypal_amount: None,
+                expected_state: Some(record.expected_state.clone()),
+                paypal_state: None,
+            }
+        }
+    };
+    let amount = remote.amount.as_deref().and_then(minor_units);
+    let amount_matches = amount.is_some()
+        && amount.map(|units| signed(record.kind, units))
+            == minor_units(&record.amount).map(|units| signed(record.kind, units))
+        && remote.currency.as_deref() == Some(record.currency.as_str());
+    let status = if !amount_matches {
+        Status::AmountMismatch
+    } else if remote.state.as_ref() != Some(&record.expected_state) {
+        Status::UnexpectedState
+    } else {
+        Status::Matched
+    };
+    Entry {
+        id: record.id.clone(),
+        status,
+        local_amount,
+        paypal_amount: paypal_amount(remote),
+        expected_state: Some(record.expected_state.clone()),
+        paypal_state: remote.state.clone(),
+    }
+}
+
+// refunds as money leaving the account, which is how transaction search reports them
+fn signed(kind: Kind, units: i64) -> i64 {
+    match kind {
+        Kind::SaleRefund | Kind::CaptureRefund => -units.abs(),
+        Kind::Payment | Kind::Sale | Kind::Capture => units,
+    }
+}
+
+// the `State` named like `status`, which is `cancelled` for refunds where `State` has `canceled`
+fn state(status: &str) -> State {
+    match status.to_lowercase().as_str() {
+        "cancelled" => State::Canceled,
+        status => State::from(status),
+    }
+}
+
+// the sum of the transactions of a payment, in thousandths, when they share one currency
+fn total(payment: &Payment) -> Option<(i64, &str)> {
+    let currency = payment.transactions.first()?.amount.currency.as_str();
+    let mut sum = 0;
+    for transaction in &payment.transactions {
+        if transaction.amount.currency != currency {
+            return None;
+        }
+        sum = minor_units(&transaction.amount.total)?.checked_add(sum)?;
+    }
+    Some((sum, currency))
+}
+
+fn paypal_amount(remote: &Remote) -> Option<String> {
+    let amount = remote.amount.as_deref()?;
+    let amount = minor_units(amount).map_or_else(|| amount.to_string(), format_units);
+    Some(format!(
+        "{} {}",
+        amount,
+        remote.currency.as_deref().unwrap_or_default()
+    ))
+}
+
+// thousandths as PayPal writes amounts: as many decimals as needed, but at least two
+fn format_units(units: i64) -> String {
+    let sign = if units < 0 { "-" } else { "" };
+    let units = units.unsigned_abs();
+    let mut amount = format!("{}{}.{:03}", sign, units / 1000, units % 1000);
+    while amount.ends_with('0') && amount.len() - amount.find('.').unwrap() > 3 {
+        amount.pop();
+    }
+    amount
+}
+
+// "12.3", "12.30" and "12.300" compare equal as 12300 thousandths, and "-0.50" as -500
+fn minor_units(amount: &str) -> Option<i64> {
+    let amount = amount.trim();
+    let (negative, amount) = match amount.strip_prefix('-') {
+        Some(amount) => (true, amount),
+        None => (false, amount.strip_prefix('+').unwrap_or(amount)),
+    };
+    let (units, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if units.is_empty() || fraction.len() > 3 || !digits(units) || !digits(fraction) {
+        return None;
+    }
+    let fraction = format!("{:0<3}", fraction);
+    let units = units
+        .parse::<i64>()
+        .ok()?
+        .checked_mul(1000)?
+        .checked_add(fraction.parse().ok()?)?;
+    Some(if negative { -units } else { units })
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payment(id: &str, total: &str, state: &str) -> Payment {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "intent": "sale",
+            "state": state,
+            "payer": { "payment_method": "paypal" },
+            "transactions": [{ "amount": { "total": total, "currency": "USD" } }],
+            "links": []
+        }))
+        .unwrap()
+    }
+
+    fn record(id: &str, amount: &str) -> Record {
+        Record {
+            id: id.into(),
+            kind: Kind::Payment,
+            amount: amount.into(),
+            currency: "USD".into(),
+            expected_state: State::Approved,
+        }
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let records = [
+            record("PAY-MATCHED", "100"),
+            record("PAY-MISSING", "5.00"),
+            record("PAY-CHEAPER", "20.00"),
+            record("PAY-FAILED", "7.50"),
+        ];
+        let payments = [
+            payment("PAY-FAILED", "7.50", "failed"),
+            payment("PAY-MATCHED", "100.00", "approved"),
+            payment("PAY-CHEAPER", "19.99", "approved"),
+            payment("PAY-EXTRA", "1.00", "approved"),
+        ];
+        let report = reconcile(&records, &payments);
+        let statuses: Vec<_> = report
+            .entries
+            .iter()
+            .map(|e| (e.id.as_str(), e.status))
+            .collect();
+        assert_eq!(
+            vec![
+                ("PAY-MATCHED", Status::Matched),
+                ("PAY-MISSING", Status::Missing),
+                ("PAY-CHEAPER", Status::AmountMismatch),
+                ("PAY-FAILED", Status::UnexpectedState),
+                ("PAY-EXTRA", Status::Unrecorded),
+            ],
+            statuses
+        );
+        assert!(!report.is_clean());
+        let csv = report.to_csv();
+        assert_eq!(
+            Some("PAY-CHEAPER,amount_mismatch,20.00 USD,19.99 USD,approved,approved"),
+            csv.lines().nth(3)
+        );
+        assert_eq!(6, csv.lines().count());
+    }
+
+    #[test]
+    fn test_duplicate_records() {
+        let records = [record("PAY-1", "1.00"), record("PAY-1", "2.00")];
+        let report = reconcile(&records, &[payment("PAY-1", "1.00", "approved")]);
+        let statuses: Vec<_> = report.entries.iter().map(|e| e.status).collect();
+        assert_eq!(vec![Status::Matched, Status::AmountMismatch], statuses);
+    }
+
+    #[test]
+    fn test_captures_refunds_and_transactions() {
+        let capture: Capture = serde_json::from_value(serde_json::json!({
+            "id": "2GG279541U471931P",
+            "status": "DECLINED",
+            "amount": { "currency_code": "USD", "value": "10.00" }
+        }))
+        .unwrap();
+        let refund: CaptureRefund = serde_json::from_value(serde_json::json!({
+            "id": "1JU08902781691411",
+            "status": "CANCELLED",
+            "amount": { "currency_code": "USD", "value": "2.50" }
+        }))
+        .unwrap();
+        let transaction: TransactionDetails = serde_json::from_value(serde_json::json!({
+            "transaction_info": {
+                "transaction_id": "5TY05013RG002845M",
+                "transaction_amount": { "currency_code": "USD", "value": "-0.50" },
+                "transaction_status": "S"
+            }
+        }))
+        .unwrap();
+        let found = [
+            Remote::from(&capture),
+            Remote::from(&refund),
+            Remote::from(&transaction),
+        ];
+        let expect = |id: &str, amount: &str, state: State| Record {
+            expected_state: state,
+            ..record(id, amount)
+        };
+        let records = [
+            expect("2GG279541U471931P", "10", State::from("declined")),
+            expect("1JU08902781691411", "2.5", State::Canceled),
+            expect("5TY05013RG002845M", "0.50", State::Completed),
+        ];
+        let report = reconcile(&records, &found);
+        let statuses: Vec<_> = report.entries.iter().map(|e| e.status).collect();
+        assert_eq!(
+            vec![Status::Matched, Status::Matched, Status::AmountMismatch],
+            statuses
+        );
+        assert_eq!(
+            Some("-0.50 USD"),
+            report.entries[2].paypal_amount.as_deref()
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_fetch_captures() {
+        use crate::types::{Money, OrderIntent, OrderRequest, PurchaseUnit};
+
+        let server = crate::testing::MockServer::start();
+        let client = server.client();
+        let order = OrderRequest {
+            intent: OrderIntent::Capture,
+            purchase_units: vec![PurchaseUnit {
+                amount: Money {
+                    currency_code: "USD".to_string(),
+                    value: "100.00".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            application_context: None,
+        };
+        let order = crate::order::create(&client, &order).unwrap();
+        server.approve_order(&order.id, "PAYER42").unwrap();
+        let order = crate::order::capture(&client, &order.id).unwrap();
+        let captured = &order.purchase_units[0].payments.as_ref().unwrap().captures[0];
+        let refund = crate::capture::refund(&client, &captured.id, None).unwrap();
+
+        let records = [
+            Record {
+                kind: Kind::Capture,
+                expected_state: State::Refunded,
+                ..record(&captured.id, "100")
+            },
+            Record {
+                kind: Kind::CaptureRefund,
+                expected_state: State::Completed,
+                ..record(&refund.id, "100.00")
+            },
+            Record {
+                kind: Kind::Capture,
+                ..record("NOSUCHCAPTURE42", "1.00")
+            },
+        ];
+        let report = fetch_and_reconcile(&client, &records).unwrap();
+        let statuses: Vec<_> = report.entries.iter().map(|e| e.status).collect();
+        assert_eq!(
+            vec![Status::Matched, Status::Matched, Status::Missing],
+            statuses
+        );
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_refund_signs() {
+        use crate::types::{Money, OrderIntent, OrderRequest, PurchaseUnit};
+
+        let server = crate::testing::MockServer::start();
+        let client = server.client();
+        let order = OrderRequest {
+            intent: OrderIntent::Capture,
+            purchase_units: vec![PurchaseUnit {
+                amount: Money {
+                    currency_code: "USD".to_string(),
+                    value: "25.00".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }],
+            application_context: None,
+        };
+        let order = crate::order::create(&client, &order).unwrap();
+        server.approve_order(&order.id, "PAYER42").unwrap();
+        let order = crate::order::capture(&client, &order.id).unwrap();
+        let captured = &order.purchase_units[0].payments.as_ref().unwrap().captures[0];
+        let refund = crate::capture::refund(&client, &captured.id, None).unwrap();
+
+        // looking the refund up gives 25.00, searching for it -25.00
+        let search = TransactionSearch::new(chrono::Utc::now(), chrono::Utc::now());
+        for amount in &["25.00", "-25.00"] {
+            let records = [Record {
+                kind: Kind::CaptureRefund,
+                expected_state: State::Completed,
+                ..record(&refund.id, amount)
+            }];
+            let fetched = fetch_and_reconcile(&client, &records).unwrap();
+            assert_eq!(Status::Matched, fetched.entries[0].status);
+            let searched = search_and_reconcile(&client, &records, &search).unwrap();
+            let entry = searched
+                .entries
+                .iter()
+                .find(|entry| entry.id == refund.id)
+                .unwrap();
+            assert_eq!(Status::Matched, entry.status);
+            assert_eq!(Some("-25.00 USD"), entry.paypal_amount.as_deref());
+        }
+
+        // the sign still matters for what is not a refund
+        let records = [Record {
+            kind: Kind::Capture,
+            expected_state: State::Refunded,
+            ..record(&captured.id, "-25.00")
+        }];
+        let fetched = fetch_and_reconcile(&client, &records).unwrap();
+        assert_eq!(Status::AmountMismatch, fetched.entries[0].status);
+    }
+
+    #[test]
+    fn test_total_overflow() {
+        let huge = payment("PAY-1", "9000000000000000", "approved");
+        assert_eq!(Some((9_000_000_000_000_000_000, "USD")), total(&huge));
+        let amount =
+            serde_json::json!({ "amount": { "total": "9000000000000000", "currency": "USD" } });
+        let huge: Payment = serde_json::from_value(serde_json::json!({
+            "id": "PAY-1",
+            "intent": "sale",
+            "state": "approved",
+            "payer": { "payment_method": "paypal" },
+            "transactions": [amount, amount],
+            "links": []
+        }))
+        .unwrap();
+        assert_eq!(None, total(&huge));
+        let report = reconcile(&[record("PAY-1", "1.00")], &[huge]);
+        assert_eq!(Status::AmountMismatch, report.entries[0].status);
+    }
+
+    #[test]
+    fn test_minor_units() {
+        assert_eq!(Some(12300), minor_units("12.3"));
+        assert_eq!(minor_units("12.30"), minor_units("12.300"));
+        assert_eq!(None, minor_units("12.3456"));
+        assert_eq!(Some(-500), minor_units("-0.50"));
+        assert_eq!(Some(-1500), minor_units(" -1.5"));
+        assert_eq!(None, minor_units("1.-5"));
+        assert_eq!(None, minor_units("--1"));
+        assert_eq!(None, minor_units("9223372036854775.808"));
+        assert_eq!("-1.50", format_units(-1500));
+        assert_eq!("-0.125", format_units(-125));
+        assert_eq!("\"a,\"\"b\"\"\"", csv_field("a,\"b\""));
+    }
+}
@@ -0,0 +1,110 @@
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+// json keys whose values are secrets, scrubbed wherever they appear in a body
+const SECRET_KEYS: &[&str] = &[
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "number",
+    "cvv2",
+];
+
+// headers carrying credentials
+const SECRET_HEADERS: &[&str] = &["authorization", "paypal-auth-assertion", "set-cookie"];
+
+pub(crate) const REDACTED: &str = "REDACTED";
+
+pub(crate) fn headers(headers: &HashMap<String, String>) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            if SECRET_HEADERS.contains(&name.to_lowercase().as_str()) {
+                (name.clone(), REDACTED.into())
+            } else {
+                (name.clone(), value.clone())
+            }
+        })
+        .collect()
+}
+
+// json bodies are kept as json, anything else as a string
+pub(crate) fn body(body: &[u8]) -> Value {
+    let body = String::from_utf8_lossy(body);
+    if body.trim().is_empty() {
+        return Value::Null;
+    }
+    match serde_json::from_str(&body) {
+        Ok(mut json) => {
+            scrub(&mut json);
+            json
+        }
+        Err(_) => Value::String(body.into_owned()),
+    }
+}
+
+// keeps the last four digits, which PayPal itself shows to payers
+pub(crate) fn card_number(number: &str) -> String {
+    let shown = number.len().saturating_sub(4);
+    match number.get(shown..) {
+        Some(last4) if shown > 0 => format!("{}{}", "*".repeat(shown), last4),
+        _ => REDACTED.into(),
+    }
+}
+
+fn scrub(json: &mut Value) {
+    match json {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_KEYS.contains(&key.as_str()) && !value.is_null() {
+                    *value = Value::String(REDACTED.into());
+                } else {
+                    scrub(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(scrub),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_body() {
+        let body = json!({
+            "access_token": "A21AAF",
+            "funding_instruments": [{
+                "credit_card": { "number": "4417119669820331", "cvv2": "874", "type": "visa" }
+            }]
+        });
+        assert_eq!(
+            json!({
+                "access_token": "REDACTED",
+                "funding_instruments": [{
+                    "credit_card": { "number": "REDACTED", "cvv2": "REDACTED", "type": "visa" }
+                }]
+            }),
+            super::body(body.to_string().as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_headers() {
+        let mut map = HashMap::new();
+        map.insert("Authorization".to_string(), "Bearer A21AAF".to_string());
+        map.insert("Content-Type".to_string(), "application/json".to_string());
+        let redacted = headers(&map);
+        assert_eq!(REDACTED, redacted["Authorization"]);
+        assert_eq!("application/json", redacted["Content-Type"]);
+    }
+
+    #[test]
+    fn test_card_number() {
+        assert_eq!("************0331", card_number("4417119669820331"));
+        assert_eq!(REDACTED, card_number("0331"));
+    }
+}
@@ -1,11 +1,22 @@
+use crate::client::Client;
 use crate::error::Error;
-use reqwest::{header::HeaderMap, Client, Response};
+use crate::metrics::Exchange;
+use crate::middleware::Next;
+use crate::rate_limit::RateLimiter;
+use crate::redact;
+use crate::response::Metadata;
+use crate::transport::{HttpRequest, HttpResponse, Transport};
+use crate::types::Scope;
+use reqwest::Method;
 use std::collections::HashMap;
-use std::str::FromStr;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
 
 pub type Resp<T> = Result<T, Error>;
 
 pub fn post<T>(
+    transport: &dyn Transport,
+    client: Option<&Client>,
     url: &str,
     headers: &HashMap<String, String>,
     form: &HashMap<String, String>,
@@ -13,52 +24,315 @@ pub fn post<T>(
 where
     T: serde::de::DeserializeOwned,
 {
-    let client = Client::new();
-    let client = client.post(url);
-    let resp = client.headers(_build_headers(headers)).form(form).send();
-    _build_response(resp)
+    let form = serde_urlencoded::to_string(form).map_err(|err| Error::new(err.to_string()))?;
+    let request = HttpRequest {
+        method: Method::POST,
+        url: url.into(),
+        headers: headers.clone(),
+        body: Some(form.into_bytes()),
+        timeout: client.and_then(Client::timeout),
+    };
+    let response = _exchange(transport, client, &request, 1)?;
+    _build_response(&request, &response)
 }
 
-pub fn get<T>(url: &str, headers: &HashMap<String, String>) -> Resp<T>
+pub fn get<T>(client: &Client, url: &str) -> Resp<T>
 where
     T: serde::de::DeserializeOwned,
 {
-    let client = Client::new();
-    let client = client.get(url);
-    let resp = client.headers(_build_headers(headers)).send();
-    _build_response(resp)
+    call::<(), T>(client, Method::GET, url, None)
 }
 
-pub fn post_json<F, T>(url: &str, headers: &mut HashMap<String, String>, form: &F) -> Resp<T>
+pub fn delete<T>(client: &Client, url: &str) -> Resp<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    call::<(), T>(client, Method::DELETE, url, None)
+}
+
+pub fn post_json<F, T>(client: &Client, url: &str, form: &F) -> Resp<T>
 where
     F: serde::Serialize + ?Sized,
     T: serde::de::DeserializeOwned,
 {
-    let client = Client::new();
-    let client = client.post(url);
-    headers.insert("Content-Type".into(), "application/json".into());
-    let resp = client.headers(_build_headers(headers)).json(form).send();
-    _build_response(resp)
-}
-
-fn _build_headers(map: &HashMap<String, String>) -> HeaderMap {
-    let mut header_map = HeaderMap::new();
-    map.iter().for_each(|(k, v)| {
-        header_map.insert(
-            reqwest::header::HeaderName::from_str(k.as_str()).unwrap(),
-            v.parse().unwrap(),
-        );
+    call(client, Method::POST, url, Some(form))
+}
+
+pub fn put_json<F, T>(client: &Client, url: &str, form: &F) -> Resp<T>
+where
+    F: serde::Serialize + ?Sized,
+    T: serde::de::DeserializeOwned,
+{
+    call(client, Method::PUT, url, Some(form))
+}
+
+pub fn patch_json<F, T>(client: &Client, url: &str, form: &F) -> Resp<T>
+where
+    F: serde::Serialize + ?Sized,
+    T: serde::de::DeserializeOwned,
+{
+    call(client, Method::PATCH, url, Some(form))
+}
+
+pub fn call<F, T>(client: &Client, method: Method, url: &str, form: Option<&F>) -> Resp<T>
+where
+    F: serde::Serialize + ?Sized,
+    T: serde::de::DeserializeOwned,
+{
+    let mut headers = client.headers()?;
+    let body = match form {
+        Some(form) => {
+            headers.insert("Content-Type".into(), "application/json".into());
+            Some(serde_json::to_vec(form)?)
+        }
+        None => None,
+    };
+    let mut request = HttpRequest {
+        method,
+        url: url.into(),
+        headers,
+        body,
+        timeout: client.timeout(),
+    };
+    let started = Instant::now();
+    let retries = client.rate_limiter().map_or(0, RateLimiter::max_retries);
+    let mut attempt = 1;
+    let mut reauthorized = false;
+    let response = loop {
+        let response = _exchange(client.transport(), Some(client), &request, attempt)?;
+        match client.rate_limiter() {
+            Some(limiter) if response.status == 429 && attempt <= retries => {
+                let delay = _retry_after(&response)
+                    .unwrap_or_else(|| _backoff(attempt))
+                    .min(limiter.max_delay());
+                tracing::info!(
+                    delay_ms = delay.as_millis() as u64,
+                    "rate limited by paypal"
+                );
+                limiter.pause(&_path_template(url), delay);
+                attempt += 1;
+            }
+            // once, as a token PayPal refuses right after handing it out will not do either
+            _ if response.status == 401 && !reauthorized && _reauthorize(client, &mut request)? => {
+                tracing::info!("access token refused by paypal, retrying with another");
+                reauthorized = true;
+                attempt += 1;
+            }
+            _ => break response,
+        }
+    };
+    // recorded before the status is checked, so that a failed call is described too
+    client.record_metadata(|| Metadata {
+        status: response.status,
+        headers: response.headers.clone(),
+        request_id: request
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("paypal-request-id"))
+            .map(|(_, id)| id.clone()),
+        raw_body: String::from_utf8_lossy(&response.body).into_owned(),
+        elapsed: started.elapsed(),
     });
-    header_map
+    _build_response(&request, &response)
+        .map_err(|err| _explain_scope(client, &request, &response, err))
+}
+
+// every request goes through here, so that it is traced and measured, and passes the client's
+// middleware
+fn _exchange(
+    transport: &dyn Transport,
+    client: Option<&Client>,
+    request: &HttpRequest,
+    attempt: u32,
+) -> Resp<HttpResponse> {
+    let endpoint = _path_template(&request.url);
+    let log_bodies = client.is_some_and(Client::logs_bodies);
+    let span = tracing::info_span!(
+        "paypal",
+        method = %request.method,
+        path = %endpoint,
+        attempt,
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+        debug_id = tracing::field::Empty,
+    );
+    let _entered = span.enter();
+    if let Some(limiter) = client.and_then(Client::rate_limiter) {
+        let waited = limiter.acquire(&endpoint);
+        if !waited.is_zero() {
+            tracing::debug!(
+                waited_ms = waited.as_millis() as u64,
+                "rate limiter delayed call"
+            );
+        }
+    }
+    if log_bodies {
+        if let Some(body) = &request.body {
+            tracing::debug!(body = %redact::body(body), "request body");
+        }
+    }
+    let started = Instant::now();
+    let middleware = client.map(Client::middleware).unwrap_or_default();
+    let response = Next::new(middleware, transport).run(request.clone());
+    let latency = started.elapsed();
+    span.record("latency_ms", latency.as_millis() as u64);
+    match &response {
+        Ok(response) => {
+            span.record("status", response.status);
+            if let Some(debug_id) = response.headers.get("paypal-debug-id") {
+                span.record("debug_id", debug_id.as_str());
+            }
+            if log_bodies {
+                tracing::debug!(body = %redact::body(&response.body), "response body");
+            }
+            tracing::debug!(status = response.status, "paypal responded");
+        }
+        Err(err) => tracing::warn!(error = %err, "paypal did not respond"),
+    }
+    let exchange = Exchange {
+        method: request.method.as_str(),
+        endpoint: &endpoint,
+        status: response.as_ref().ok().map(|response| response.status),
+        latency,
+        attempt,
+    };
+    #[cfg(feature = "metrics")]
+    crate::metrics::record(&exchange);
+    for hook in client.map(Client::metrics).unwrap_or_default() {
+        hook.exchange(&exchange);
+    }
+    response
 }
 
-fn _build_response<T>(mut r: reqwest::Result<Response>) -> Resp<T>
+// PayPal refuses calls the token lacks the scope for with `403 Forbidden`, without saying which
+// scope. The endpoint tells, and a client managing its token knows whether the token has it.
+fn _explain_scope(
+    client: &Client,
+    request: &HttpRequest,
+    response: &HttpResponse,
+    err: Error,
+) -> Error {
+    if response.status != 403 {
+        return err;
+    }
+    let accepted = Scope::accepted_for(&_path_template(&request.url));
+    if accepted.is_empty() {
+        return err;
+    }
+    match client.token_scopes() {
+        Some(scopes) if accepted.iter().any(|scope| scopes.contains(scope)) => err,
+        Some(_) => err.with_scope_hint(accepted, true),
+        None => err.with_scope_hint(accepted, false),
+    }
+}
+
+// swaps the access token PayPal refused for another, when the client requests its own
+fn _reauthorize(client: &Client, request: &mut HttpRequest) -> Resp<bool> {
+    let rejected = request
+        .headers
+        .get("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let token = match rejected {
+        Some(rejected) => client.replace_token(rejected)?,
+        None => None,
+    };
+    let token = match token {
+        Some(token) => token,
+        None => return Ok(false),
+    };
+    let refused = request.headers.insert(
+        "Authorization".into(),
+        format!("Bearer {}", token.expose_secret()),
+    );
+    drop(refused.map(Zeroizing::new));
+    Ok(true)
+}
+
+// PayPal sends the number of seconds to wait
+fn _retry_after(response: &HttpResponse) -> Option<Duration> {
+    let seconds = response.headers.get("retry-after")?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+// 1, 2, 4... seconds, when PayPal does not say how long to wait
+fn _backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1 << (attempt - 1).min(5))
+}
+
+// the end of the middleware chain, where a cassette can record or replay the request in tests
+pub(crate) fn transmit(transport: &dyn Transport, request: &HttpRequest) -> Resp<HttpResponse> {
+    let perform = || transport.send(request);
+    #[cfg(any(test, feature = "testing"))]
+    {
+        if let Some(response) = crate::cassette::intercept(request, perform) {
+            return response;
+        }
+    }
+    perform()
+}
+
+// ids are left out of the traced path, so that calls to the same endpoint can be grouped. They
+// are the long path segments containing digits, which keeps `v1` and `oauth2` as they are.
+fn _path_template(url: &str) -> String {
+    let path = url.split('?').next().unwrap_or_default();
+    let path = match path.find("://") {
+        Some(scheme) => &path[scheme + 3..],
+        None => path,
+    };
+    let path = path.find('/').map_or("/", |start| &path[start..]);
+    path.split('/')
+        .map(|segment| {
+            if segment.len() >= 10 && segment.bytes().any(|b| b.is_ascii_digit()) {
+                "{id}"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn _build_response<T>(request: &HttpRequest, response: &HttpResponse) -> Resp<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    if (200..300).contains(&response.status) {
+        _parse_body(&response.body)
+    } else {
+        Err(Error::from_response(request, response))
+    }
+}
+
+// endpoints answering `204 No Content` are modelled as returning `()`, which serde reads from null
+fn _parse_body<T>(body: &[u8]) -> Resp<T>
 where
     T: serde::de::DeserializeOwned,
 {
-    match r {
-        Ok(ref mut r) if r.status().is_success() => Ok(r.json().unwrap()),
-        Ok(r) => Err(r.into()),
-        Err(err) => Err(err.into()),
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(serde_json::from_str("null")?);
+    }
+    Ok(serde_json::from_slice(body)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_template() {
+        assert_eq!(
+            "/v1/payments/payment/{id}/execute",
+            _path_template(
+                "https://api.paypal.com/v1/payments/payment/PAY-1B56960729604235TKQQIYVY/execute"
+            )
+        );
+        assert_eq!(
+            "/v1/oauth2/token",
+            _path_template("http://127.0.0.1:8080/v1/oauth2/token")
+        );
+        assert_eq!(
+            "/v1/payments/payment",
+            _path_template("https://api.paypal.com/v1/payments/payment?count=10")
+        );
     }
 }
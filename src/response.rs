@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The value a call returned, together with what else PayPal's response carried. Obtained by
+/// running the call through `Client::with_response`.
+#[derive(Clone, Debug)]
+pub struct Response<T> {
+    /// The deserialized body.
+    pub value: T,
+    /// The HTTP status code.
+    pub status: u16,
+    /// The response headers. Names are lowercase.
+    pub headers: HashMap<String, String>,
+    /// The `Paypal-Debug-Id` header, which PayPal support asks for when investigating a call.
+    pub debug_id: Option<String>,
+    /// The `PayPal-Request-Id` the call was made with, if any, which makes retrying it safe.
+    pub request_id: Option<String>,
+    /// The body exactly as PayPal sent it.
+    pub raw_body: String,
+    /// How long the call took, including any retries and rate limiting.
+    pub elapsed: Duration,
+}
+
+impl<T> Response<T> {
+    /// The value of the header `name`, which is case insensitive.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    /// Drops the metadata, keeping the deserialized body.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+// everything but the value, captured by the request module for `Client::with_response`
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Metadata {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub request_id: Option<String>,
+    pub raw_body: String,
+    pub elapsed: Duration,
+}
+
+impl Metadata {
+    pub(crate) fn with_value<T>(self, value: T) -> Response<T> {
+        Response {
+            value,
+            debug_id: self.headers.get("paypal-debug-id").cloned(),
+            status: self.status,
+            headers: self.headers,
+            request_id: self.request_id,
+            raw_body: self.raw_body,
+            elapsed: self.elapsed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::{HttpRequest, HttpResponse, Transport};
+    use crate::Client;
+
+    struct Created;
+
+    impl Transport for Created {
+        fn send(&self, _: &HttpRequest) -> Result<HttpResponse, crate::error::Error> {
+            let mut headers = std::collections::HashMap::new();
+            headers.insert("paypal-debug-id".to_string(), "f0d4c9d2e3a1b".to_string());
+            headers.insert(
+                "location".to_string(),
+                "/v1/payments/payment/PAY-1".to_string(),
+            );
+            Ok(HttpResponse {
+                status: 201,
+                headers,
+                body: br#"{"payments": [], "count": 0}"#.to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_with_response() {
+        let client = Client::new("token")
+            .with_transport(Created)
+            .with_header("PayPal-Request-Id", "order-42");
+        let response = client.with_response(crate::payment::list).unwrap();
+        assert_eq!(0, response.value.count);
+        assert_eq!(201, response.status);
+        assert_eq!(Some("f0d4c9d2e3a1b"), response.debug_id.as_deref());
+        assert_eq!(Some("order-42"), response.request_id.as_deref());
+        assert_eq!(
+            Some("/v1/payments/payment/PAY-1"),
+            response.header("Location")
+        );
+        assert_eq!(r#"{"payments": [], "count": 0}"#, response.raw_body);
+    }
+
+    struct NotFound;
+
+    impl Transport for NotFound {
+        fn send(&self, _: &HttpRequest) -> Result<HttpResponse, crate::error::Error> {
+            let mut headers = std::collections::HashMap::new();
+            headers.insert("paypal-debug-id".to_string(), "a1b2c3d4e5f6".to_string());
+            Ok(HttpResponse {
+                status: 404,
+                headers,
+                body: br#"{"name":"INVALID_RESOURCE_ID","debug_id":"a1b2c3d4e5f6"}"#.to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_failed_call() {
+        let client = Client::new("token").with_transport(NotFound);
+        let err = client
+            .with_response(|client| crate::payment::get(client, "PAY-0"))
+            .unwrap_err();
+        assert_eq!(Some(404), err.status());
+        assert_eq!(Some("a1b2c3d4e5f6"), err.debug_id());
+
+        // a call that recovers from the failure still sees the response it failed with
+        let response = client
+            .with_response(|client| Ok(crate::payment::get(client, "PAY-0").err()))
+            .unwrap();
+        assert!(response.value.is_some());
+        assert_eq!(404, response.status);
+        assert_eq!(Some("a1b2c3d4e5f6"), response.debug_id.as_deref());
+        assert!(response.raw_body.contains("INVALID_RESOURCE_ID"));
+    }
+}
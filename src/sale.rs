@@ -0,0 +1,95 @@
+use crate::client::Client;
+use crate::request::Resp;
+use crate::types::{Refund, RequestRefund, Sale, TransactionAmount};
+
+/// Shows the details of a sale, the completed transaction of an executed v1 payment, by ID.
+pub fn get(client: &Client, sale_id: &str) -> Resp<Sale> {
+    crate::request::get(
+        client,
+        &client.endpoint(&format!("/v1/payments/sale/{}", sale_id)),
+    )
+}
+
+/// Refunds a sale, either in full or, when `amount` is given, in part.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, sale, Client, TransactionAmount};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let amount = TransactionAmount {
+///     currency: "USD".to_string(),
+///     total: "10.00".to_string(),
+///     ..Default::default()
+/// };
+/// let client = Client::new(&token.access_token);
+/// let refund = sale::refund(&client, "2MU78835H4515710F", Some(&amount)).unwrap();
+/// ```
+pub fn refund(client: &Client, sale_id: &str, amount: Option<&TransactionAmount>) -> Resp<Refund> {
+    crate::request::post_json(
+        client,
+        &client.endpoint(&format!("/v1/payments/sale/{}/refund", sale_id)),
+        &RequestRefund { amount },
+    )
+}
+
+/// Shows the details of a refund of a sale, by ID.
+pub fn get_refund(client: &Client, refund_id: &str) -> Resp<Refund> {
+    crate::request::get(
+        client,
+        &client.endpoint(&format!("/v1/payments/refund/{}", refund_id)),
+    )
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::types::{PaymentIntent, PaymentMethod, State, Transaction};
+
+    #[test]
+    fn test_refund() {
+        let server = crate::testing::MockServer::start();
+        let client = server.client();
+        let amount = TransactionAmount {
+            currency: "USD".to_string(),
+            total: "100.00".to_string(),
+            ..Default::default()
+        };
+        let payment = crate::payment::new(
+            &client,
+            "https://mysite.com/whooyoupaid",
+            "https://mysite.com/nooyoufailed",
+            PaymentMethod::Paypal,
+            PaymentIntent::Sale,
+            vec![Transaction {
+                amount,
+                ..Default::default()
+            }],
+        )
+        .unwrap();
+        server.approve_payment(&payment.id, "PAYER42").unwrap();
+        let executed = crate::payment::execute(&client, &payment.id, "PAYER42").unwrap();
+        let sale_id = executed.transactions[0].extra["related_resources"][0]["sale"]["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let sale = get(&client, &sale_id).unwrap();
+        assert_eq!(Some(payment.id.as_str()), sale.parent_payment.as_deref());
+
+        let partial = TransactionAmount {
+            currency: "USD".to_string(),
+            total: "10.00".to_string(),
+            ..Default::default()
+        };
+        let refund = refund(&client, &sale_id, Some(&partial)).unwrap();
+        assert_eq!(Some(State::Completed), refund.state);
+        assert_eq!(Some(sale_id.as_str()), refund.sale_id.as_deref());
+        assert_eq!(
+            "10.00",
+            get_refund(&client, &refund.id)
+                .unwrap()
+                .amount
+                .unwrap()
+                .total
+        );
+    }
+}
@@ -0,0 +1,927 @@
+use crate::client::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The client id the mock server accepts when issuing tokens.
+pub const CLIENT_ID: &str = "mock-client-id";
+/// The secret the mock server accepts when issuing tokens.
+pub const SECRET: &str = "mock-secret";
+
+/// A local HTTP server emulating PayPal's OAuth token, payments and orders endpoints, so that
+/// code using this crate can be tested without credentials or network access.
+///
+/// Payments and orders move through the same states as on PayPal: they are created, approved by
+/// the payer (simulated with `approve_payment` and `approve_order`), executed or captured, and
+/// finally refunded. The server stops when it is dropped.
+///
+/// ```rust,no_run
+/// use paypal::testing::MockServer;
+/// use paypal::{payment, PaymentIntent, PaymentMethod, Transaction, TransactionAmount};
+///
+/// let server = MockServer::start();
+/// let client = server.client();
+/// let amount = TransactionAmount {
+///     currency: "USD".to_string(),
+///     total: "100.00".to_string(),
+///     ..Default::default()
+/// };
+/// let created = payment::new(
+///     &client,
+///     "https://mysite.com/whooyoupaid",
+///     "https://mysite.com/nooyoufailed",
+///     PaymentMethod::Paypal,
+///     PaymentIntent::Sale,
+///     vec![Transaction { amount, ..Default::default() }],
+/// ).unwrap();
+/// server.approve_payment(&created.id, "PAYER42").unwrap();
+/// let executed = payment::execute(&client, &created.id, "PAYER42").unwrap();
+/// ```
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Starts a mock server on a free local port.
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("a free local port");
+        let addr = listener.local_addr().expect("a bound address");
+        let state = Arc::new(Mutex::new(State {
+            base_url: format!("http://{}", addr),
+            ..Default::default()
+        }));
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let state = state.clone();
+            let running = running.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if !running.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        serve(stream, &state);
+                    }
+                }
+            })
+        };
+        Self {
+            addr,
+            state,
+            running,
+            thread: Some(thread),
+        }
+    }
+
+    /// The base url of the server, to pass to `Client::with_base_url` or `get_token_from`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A client that is already authenticated against this server.
+    pub fn client(&self) -> Client {
+        let token = self.state.lock().unwrap().issue_token();
+        Client::new(&token).with_base_url(&self.url())
+    }
+
+    /// Simulates the payer approving a payment on the PayPal site. Returns the url the payer is
+    /// redirected to, which carries the `paymentId`, `token` and `PayerID` query parameters, or
+    /// `None` if there is no such payment waiting for approval.
+    pub fn approve_payment(&self, payment_id: &str, payer_id: &str) -> Option<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .approve_payment(payment_id, payer_id)
+    }
+
+    /// Simulates the payer approving an order on the PayPal site. Returns the url the payer is
+    /// redirected to, which carries the `token` and `PayerID` query parameters, or `None` if
+    /// there is no such order waiting for approval.
+    pub fn approve_order(&self, order_id: &str, payer_id: &str) -> Option<String> {
+        self.state.lock().unwrap().approve_order(order_id, payer_id)
+    }
+
+    /// Makes the next `times` requests whose method is `method` and whose path starts with
+    /// `path` fail with the given status code and a PayPal-style error body.
+    pub fn fail(&self, method: &str, path: &str, status: u16, times: usize) {
+        self.state.lock().unwrap().failures.push(Failure {
+            method: method.to_uppercase(),
+            path: path.into(),
+            status,
+            remaining: times,
+        });
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        // wake up the accept loop so it notices it should stop
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct Failure {
+    method: String,
+    path: String,
+    status: u16,
+    remaining: usize,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+struct Reply {
+    status: u16,
+    body: Option<Value>,
+}
+
+impl Reply {
+    fn json(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            body: Some(body),
+        }
+    }
+
+    fn error(status: u16, name: &str, message: &str) -> Self {
+        Self::json(
+            status,
+            json!({
+                "name": name,
+                "message": message,
+                "debug_id": "mockdebugid",
+                "details": [],
+            }),
+        )
+    }
+
+    fn not_found() -> Self {
+        Self::error(
+            404,
+            "RESOURCE_NOT_FOUND",
+            "The specified resource does not exist.",
+        )
+    }
+}
+
+#[derive(Default)]
+struct State {
+    base_url: String,
+    next_id: u64,
+    tokens: Vec<String>,
+    payments: HashMap<String, Value>,
+    // payment id -> payer id, for payments the payer approved but that were not executed yet
+    approvals: HashMap<String, String>,
+    sales: HashMap<String, Value>,
+    orders: HashMap<String, Value>,
+    captures: HashMap<String, Value>,
+    refunds: HashMap<String, Value>,
+    failures: Vec<Failure>,
+}
+
+impl State {
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}{:08}", prefix, self.next_id)
+    }
+
+    fn link(&self, path: &str, rel: &str, method: &str) -> Value {
+        json!({ "href": format!("{}{}", self.base_url, path), "rel": rel, "method": method })
+    }
+
+    fn issue_token(&mut self) -> String {
+        let token = self.next_id("A21AAMOCKTOKEN");
+        self.tokens.push(token.clone());
+        token
+    }
+
+    fn handle(&mut self, request: &Request) -> Reply {
+        if let Some(failure) = self.failures.iter_mut().find(|failure| {
+            failure.remaining > 0
+                && failure.method == request.method
+                && request.path.starts_with(&failure.path)
+        }) {
+            failure.remaining -= 1;
+            return Reply::error(failure.status, "INJECTED_FAILURE", "Injected by the mock.");
+        }
+        let path = request.path.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        if let ("POST", ["v1", "oauth2", "token"]) = (request.method.as_str(), &segments[..]) {
+            return self.token(request);
+        }
+        let authorized = request
+            .headers
+            .get("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| self.tokens.iter().any(|known| known == token));
+        if !authorized {
+            return Reply::json(
+                401,
+                json!({
+                    "error": "invalid_token",
+                    "error_description": "Token signature verification failed",
+                }),
+            );
+        }
+        let body: Value = serde_json::from_slice(&request.body).unwrap_or(Value::Null);
+        match (request.method.as_str(), &segments[..]) {
+            ("POST", ["v1", "payments", "payment"]) => self.create_payment(body),
+            ("GET", ["v1", "payments", "payment"]) => self.list_payments(),
+            ("GET", ["v1", "payments", "payment", id]) => self.render_payment(id),
+            ("POST", ["v1", "payments", "payment", id, "execute"]) => {
+                self.execute_payment(id, body)
+            }
+            ("GET", ["v1", "payments", "sale", id]) => self.show(&self.sales, id),
+            ("POST", ["v1", "payments", "sale", id, "refund"]) => self.refund_sale(id, body),
+            ("GET", ["v1", "payments", "refund", id]) => self.show(&self.refunds, id),
+            ("POST", ["v2", "checkout", "orders"]) => self.create_order(body),
+            ("GET", ["v2", "checkout", "orders", id]) => self.render_order(id),
+            ("PATCH", ["v2", "checkout", "orders", id]) => self.update_order(id, body),
+            ("POST", ["v2", "checkout", "orders", id, "capture"]) => self.capture_order(id),
+            ("GET", ["v2", "payments", "captures", id]) => self.show(&self.captures, id),
+            ("POST", ["v2", "payments", "captures", id, "refund"]) => self.refund_capture(id, body),
+            ("GET", ["v2", "payments", "refunds", id]) => self.show(&self.refunds, id),
+            ("GET", ["v1", "reporting", "transactions"]) => self.search_transactions(),
+            _ => Reply::not_found(),
+        }
+    }
+
+    fn token(&mut self, request: &Request) -> Reply {
+        let expected = format!(
+            "Basic {}",
+            base64::encode(&format!("{}:{}", CLIENT_ID, SECRET))
+        );
+        if request.headers.get("authorization") != Some(&expected) {
+            return Reply::json(
+                401,
+                json!({
+                    "error": "invalid_client",
+                    "error_description": "Client Authentication failed",
+                }),
+            );
+        }
+        let access_token = self.issue_token();
+        Reply::json(
+            200,
+            json!({
+                "scope": "https://uri.paypal.com/services/payments/payment openid",
+                "nonce": "2019-10-01T12:00:00ZmockNonce",
+                "access_token": access_token,
+                "token_type": "Bearer",
+                "app_id": "APP-80W284485P519543T",
+                "expires_in": 32400,
+            }),
+        )
+    }
+
+    fn show(&self, resources: &HashMap<String, Value>, id: &str) -> Reply {
+        match resources.get(id) {
+            Some(resource) => Reply::json(200, resource.clone()),
+            None => Reply::not_found(),
+        }
+    }
+
+    fn create_payment(&mut self, body: Value) -> Reply {
+        let id = self.next_id("PAYID-MOCK");
+        let token = self.next_id("EC-");
+        let payment = json!({
+            "id": id,
+            "intent": body["intent"],
+            "state": "created",
+            "payer": body["payer"],
+            "transactions": body["transactions"],
+            "redirect_urls": body["redirect_urls"],
+            "create_time": now(),
+            "links": [
+                self.link(&format!("/v1/payments/payment/{}", id), "self", "GET"),
+                self.link(&format!("/checkoutnow?token={}", token), "approval_url", "REDIRECT"),
+                self.link(&format!("/v1/payments/payment/{}/execute", id), "execute", "POST"),
+            ],
+        });
+        self.payments.insert(id.clone(), payment);
+        self.render_payment(&id).with_status(201)
+    }
+
+    fn list_payments(&self) -> Reply {
+        let mut ids: Vec<&String> = self.payments.keys().collect();
+        ids.sort();
+        let payments: Vec<Value> = ids
+            .into_iter()
+            .filter_map(|id| self.render_payment(id).body)
+            .collect();
+        Reply::json(
+            200,
+            json!({ "count": payments.len(), "payments": payments }),
+        )
+    }
+
+    // every sale, capture and refund, on one page whatever the dates asked for. Like PayPal,
+    // refunds are money leaving the account, so their amounts are negative
+    fn search_transactions(&self) -> Reply {
+        let mut found: Vec<(&String, &Value, bool)> = Vec::new();
+        found.extend(self.sales.iter().map(|(id, sale)| (id, sale, false)));
+        found.extend(
+            self.captures
+                .iter()
+                .map(|(id, capture)| (id, capture, false)),
+        );
+        found.extend(self.refunds.iter().map(|(id, refund)| (id, refund, true)));
+        found.sort_by_key(|(id, _, _)| id.as_str());
+        let details: Vec<Value> = found
+            .into_iter()
+            .map(|(id, resource, refund)| {
+                // v1 resources have a `total` and a `currency`, v2 ones a `value` and a
+                // `currency_code`
+                let amount = &resource["amount"];
+                let value = amount["value"]
+                    .as_str()
+                    .or_else(|| amount["total"].as_str());
+                let currency = amount["currency_code"]
+                    .as_str()
+                    .or_else(|| amount["currency"].as_str());
+                let sign = if refund { "-" } else { "" };
+                json!({
+                    "transaction_info": {
+                        "transaction_id": id,
+                        "transaction_event_code": if refund { "T1107" } else { "T0006" },
+                        "transaction_amount": {
+                            "currency_code": currency,
+                            "value": format!("{}{}", sign, value.unwrap_or("0.00")),
+                        },
+                        "transaction_status": "S",
+                    }
+                })
+            })
+            .collect();
+        Reply::json(
+            200,
+            json!({ "transaction_details": details, "page": 1, "total_pages": 1 }),
+        )
+    }
+
+    // payments are stored without their sales, which are embedded with their current state
+    fn render_payment(&self, id: &str) -> Reply {
+        let mut payment = match self.payments.get(id) {
+            Some(payment) => payment.clone(),
+            None => return Reply::not_found(),
+        };
+        if let Some(transactions) = payment["transactions"].as_array_mut() {
+            for (index, transaction) in transactions.iter_mut().enumerate() {
+                let sale_id = format!("{}-SALE{}", id, index);
+                if let Some(sale) = self.sales.get(&sale_id) {
+                    transaction["related_resources"] = json!([{ "sale": sale }]);
+                }
+            }
+        }
+        Reply::json(200, payment)
+    }
+
+    fn approve_payment(&mut self, id: &str, payer_id: &str) -> Option<String> {
+        let payment = self.payments.get(id)?;
+        if payment["state"] != "created" {
+            return None;
+        }
+        let approval = payment["links"]
+            .as_array()?
+            .iter()
+            .find(|link| link["rel"] == "approval_url")?;
+        let token = approval["href"].as_str()?.split("token=").nth(1)?;
+        let return_url = payment["redirect_urls"]["return_url"].as_str()?;
+        let redirect = format!(
+            "{}{}paymentId={}&token={}&PayerID={}",
+            return_url,
+            if return_url.contains('?') { "&" } else { "?" },
+            id,
+            token,
+            payer_id
+        );
+        self.approvals.insert(id.into(), payer_id.into());
+        Some(redirect)
+    }
+
+    fn execute_payment(&mut self, id: &str, body: Value) -> Reply {
+        let state = match self.payments.get(id) {
+            Some(payment) => payment["state"].clone(),
+            None => return Reply::not_found(),
+        };
+        if state != "created" {
+            return Reply::error(
+                400,
+                "PAYMENT_STATE_INVALID",
+                "This request is invalid due to the current state of the payment",
+            );
+        }
+        let payer_id = match self.approvals.get(id) {
+            Some(payer_id) => payer_id.clone(),
+            None => {
+                return Reply::error(
+                    400,
+                    "PAYMENT_NOT_APPROVED_FOR_EXECUTION",
+                    "Payer has not approved payment",
+                )
+            }
+        };
+        if body["payer_id"] != payer_id.as_str() {
+            return Reply::error(400, "INVALID_PAYER_ID", "Payer ID is invalid");
+        }
+        self.approvals.remove(id);
+        let transactions = self.payments[id]["transactions"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        for (index, transaction) in transactions.iter().enumerate() {
+            let sale_id = format!("{}-SALE{}", id, index);
+            let sale = json!({
+                "id": sale_id,
+                "state": "completed",
+                "amount": transaction["amount"],
+                "parent_payment": id,
+                "create_time": now(),
+                "links": [
+                    self.link(&format!("/v1/payments/sale/{}", sale_id), "self", "GET"),
+                    self.link(&format!("/v1/payments/sale/{}/refund", sale_id), "refund", "POST"),
+                    self.link(&format!("/v1/payments/payment/{}", id), "parent_payment", "GET"),
+                ],
+            });
+            self.sales.insert(sale_id, sale);
+        }
+        let payment = self.payments.get_mut(id).unwrap();
+        payment["state"] = json!("approved");
+        payment["payer"]["status"] = json!("VERIFIED");
+        payment["payer"]["payer_info"] = json!({ "payer_id": payer_id });
+        payment["update_time"] = json!(now());
+        self.render_payment(id)
+    }
+
+    fn refund_sale(&mut self, id: &str, body: Value) -> Reply {
+        let sale = match self.sales.get(id) {
+            Some(sale) => sale.clone(),
+            None => return Reply::not_found(),
+        };
+        if sale["state"] != "completed" {
+            return Reply::error(400, "TRANSACTION_REFUSED", "The request was refused");
+        }
+        let refund_id = self.next_id("REFUND-MOCK");
+        let amount = match &body["amount"] {
+            Value::Null => sale["amount"].clone(),
+            amount => amount.clone(),
+        };
+        let refund = json!({
+            "id": refund_id,
+            "state": "completed",
+            "amount": amount,
+            "sale_id": id,
+            "parent_payment": sale["parent_payment"],
+            "create_time": now(),
+            "links": [
+                self.link(&format!("/v1/payments/refund/{}", refund_id), "self", "GET"),
+                self.link(&format!("/v1/payments/sale/{}", id), "sale", "GET"),
+            ],
+        });
+        self.refunds.insert(refund_id, refund.clone());
+        self.sales.get_mut(id).unwrap()["state"] = json!("refunded");
+        Reply::json(201, refund)
+    }
+
+    fn create_order(&mut self, body: Value) -> Reply {
+        let id = self.next_id("MOCKORDER");
+        let mut units = body["purchase_units"].clone();
+        // like PayPal, which names a purchase unit without a reference ID `default`
+        for unit in units.as_array_mut().into_iter().flatten() {
+            if unit["reference_id"].is_null() {
+                unit["reference_id"] = json!("default");
+            }
+        }
+        let order = json!({
+            "id": id,
+            "intent": body["intent"],
+            "status": "CREATED",
+            "purchase_units": units,
+            "application_context": body["application_context"],
+            "create_time": now(),
+            "links": [
+                self.link(&format!("/v2/checkout/orders/{}", id), "self", "GET"),
+                self.link(&format!("/checkoutnow?token={}", id), "approve", "GET"),
+                self.link(&format!("/v2/checkout/orders/{}", id), "update", "PATCH"),
+                self.link(&format!("/v2/checkout/orders/{}/capture", id), "capture", "POST"),
+            ],
+        });
+        self.orders.insert(id.clone(), order);
+        self.render_order(&id).with_status(201)
+    }
+
+    fn update_order(&mut self, id: &str, body: Value) -> Reply {
+        let order = match self.orders.get_mut(id) {
+            Some(order) => order,
+            None => return Reply::not_found(),
+        };
+        if order["status"] != "CREATED" && order["status"] != "APPROVED" {
+            return Reply::error(
+                422,
+                "UNPROCESSABLE_ENTITY",
+                "The requested action could not be performed, semantically incorrect, or failed business validation.",
+            );
+        }
+        // the patch applies to a copy, so that a failing operation leaves the order untouched
+        let mut patched = order.clone();
+        let ops = body.as_array().cloned().unwrap_or_default();
+        if ops.is_empty() || !ops.iter().all(|op| apply_patch(&mut patched, op).is_some()) {
+            return Reply::error(
+                400,
+                "INVALID_REQUEST",
+                "Request is not well-formed, syntactically incorrect, or violates schema.",
+            );
+        }
+        patched["update_time"] = json!(now());
+        *order = patched;
+        Reply {
+            status: 204,
+            body: None,
+        }
+    }
+
+    // orders are stored without their captures, which are embedded with their current state
+    fn render_order(&self, id: &str) -> Reply {
+        let mut order = match self.orders.get(id) {
+            Some(order) => order.clone(),
+            None => return Reply::not_found(),
+        };
+        if let Some(units) = order["purchase_units"].as_array_mut() {
+            for (index, unit) in units.iter_mut().enumerate() {
+                let capture_id = format!("{}-CAPTURE{}", id, index);
+                if let Some(capture) = self.captures.get(&capture_id) {
+                    unit["payments"] = json!({ "captures": [capture] });
+                }
+            }
+        }
+        Reply::json(200, order)
+    }
+
+    fn approve_order(&mut self, id: &str, payer_id: &str) -> Option<String> {
+        let order = self.orders.get_mut(id)?;
+        if order["status"] != "CREATED" {
+            return None;
+        }
+        order["status"] = json!("APPROVED");
+        order["payer"] = json!({ "payer_id": payer_id });
+        let return_url = order["application_context"]["return_url"]
+            .as_str()
+            .map(String::from)
+            .unwrap_or_else(|| format!("{}/return", self.base_url));
+        Some(format!(
+            "{}{}token={}&PayerID={}",
+            return_url,
+            if return_url.contains('?') { "&" } else { "?" },
+            id,
+            payer_id
+        ))
+    }
+
+    fn capture_order(&mut self, id: &str) -> Reply {
+        let order = match self.orders.get(id) {
+            Some(order) => order.clone(),
+            None => return Reply::not_found(),
+        };
+        if order["status"] != "APPROVED" {
+            let mut reply = Reply::error(
+                422,
+                "UNPROCESSABLE_ENTITY",
+                "The requested action could not be performed, semantically incorrect, or failed business validation.",
+            );
+            reply.body.as_mut().unwrap()["details"] = if order["status"] == "COMPLETED" {
+                json!([{
+                    "issue": "ORDER_ALREADY_CAPTURED",
+                    "description": "Order already captured.If 'intent=CAPTURE' only one capture per order is allowed.",
+                }])
+            } else {
+                json!([{
+                    "issue": "ORDER_NOT_APPROVED",
+                    "description": "Payer has not yet approved the Order for payment.",
+                }])
+            };
+            return reply;
+        }
+        let units = order["purchase_units"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        for (index, unit) in units.iter().enumerate() {
+            let capture_id = format!("{}-CAPTURE{}", id, index);
+            let capture = json!({
+                "id": capture_id,
+                "status": "COMPLETED",
+                "amount": unit["amount"],
+                "final_capture": true,
+                "create_time": now(),
+                "links": [
+                    self.link(&format!("/v2/payments/captures/{}", capture_id), "self", "GET"),
+                    self.link(&format!("/v2/payments/captures/{}/refund", capture_id), "refund", "POST"),
+                    self.link(&format!("/v2/checkout/orders/{}", id), "up", "GET"),
+                ],
+            });
+            self.captures.insert(capture_id, capture);
+        }
+        let order = self.orders.get_mut(id).unwrap();
+        order["status"] = json!("COMPLETED");
+        order["update_time"] = json!(now());
+        self.render_order(id).with_status(201)
+    }
+
+    fn refund_capture(&mut self, id: &str, body: Value) -> Reply {
+        let capture = match self.captures.get(id) {
+            Some(capture) => capture.clone(),
+            None => return Reply::not_found(),
+        };
+        if capture["status"] != "COMPLETED" {
+            return Reply::error(
+                422,
+                "UNPROCESSABLE_ENTITY",
+                "The capture has already been fully refunded.",
+            );
+        }
+        let refund_id = self.next_id("MOCKREFUND");
+        let amount = match &body["amount"] {
+            Value::Null => capture["amount"].clone(),
+            amount => amount.clone(),
+        };
+        let refund = json!({
+            "id": refund_id,
+            "status": "COMPLETED",
+            "amount": amount,
+            "create_time": now(),
+            "links": [
+                self.link(&format!("/v2/payments/refunds/{}", refund_id), "self", "GET"),
+                self.link(&format!("/v2/payments/captures/{}", id), "up", "GET"),
+            ],
+        });
+        self.refunds.insert(refund_id, refund.clone());
+        self.captures.get_mut(id).unwrap()["status"] = json!("REFUNDED");
+        Reply::json(201, refund)
+    }
+}
+
+impl Reply {
+    fn with_status(mut self, status: u16) -> Self {
+        if self.status == 200 {
+            self.status = status;
+        }
+        self
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+fn serve(stream: TcpStream, state: &Mutex<State>) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let request = match read_request(&stream) {
+        Some(request) => request,
+        None => return,
+    };
+    let reply = state.lock().unwrap().handle(&request);
+    let _ = write_reply(stream, reply);
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let mut header = line.splitn(2, ':');
+        let name = header.next()?.trim().to_lowercase();
+        let value = header.next().unwrap_or_default().trim().to_string();
+        headers.insert(name, value);
+    }
+    let length = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+    Some(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+// applies one JSON Patch operation, where a segment may also be one of PayPal's
+// `@field=='value'` filters on the elements of an array
+fn apply_patch(document: &mut Value, op: &Value) -> Option<()> {
+    let path = op["path"].as_str()?.strip_prefix('/')?;
+    let mut segments: Vec<String> = path
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    let last = segments.pop()?;
+    let mut target = document;
+    for segment in &segments {
+        target = child(target, segment)?;
+    }
+    match (op["op"].as_str()?, target) {
+        ("remove", Value::Object(fields)) => fields.remove(&last).map(drop),
+        ("remove", Value::Array(items)) => {
+            let index = last.parse().ok().filter(|index| *index < items.len())?;
+            items.remove(index);
+            Some(())
+        }
+        ("add", Value::Array(items)) if last == "-" => {
+            items.push(op.get("value")?.clone());
+            Some(())
+        }
+        ("add", Value::Object(fields)) => {
+            fields.insert(last, op.get("value")?.clone());
+            Some(())
+        }
+        ("add", Value::Array(items)) => {
+            let index = last.parse().ok().filter(|index| *index <= items.len())?;
+            items.insert(index, op.get("value")?.clone());
+            Some(())
+        }
+        ("replace", target) => {
+            *child(target, &last)? = op.get("value")?.clone();
+            Some(())
+        }
+        _ => None,
+    }
+}
+
+fn child<'a>(value: &'a mut Value, segment: &str) -> Option<&'a mut Value> {
+    let filter = segment
+        .strip_prefix('@')
+        .and_then(|filter| filter.split_once("=="));
+    match (value, filter) {
+        (Value::Array(items), Some((field, quoted))) => {
+            let wanted = quoted.strip_prefix('\'')?.strip_suffix('\'')?;
+            items.iter_mut().find(|item| item[field] == wanted)
+        }
+        (Value::Array(items), None) => items.get_mut(segment.parse::<usize>().ok()?),
+        (Value::Object(fields), None) => fields.get_mut(segment),
+        _ => None,
+    }
+}
+
+fn write_reply(mut stream: TcpStream, reply: Reply) -> std::io::Result<()> {
+    let body = reply.body.map(|body| body.to_string()).unwrap_or_default();
+    let reason = match reply.status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nPaypal-Debug-Id: mockdebugid\r\nConnection: close\r\n\r\n{}",
+        reply.status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        LinkRel, PaymentIntent, PaymentMethod, State, Transaction, TransactionAmount,
+    };
+
+    fn new_payment(client: &Client) -> crate::request::Resp<crate::types::Payment> {
+        let amount = TransactionAmount {
+            currency: "USD".to_string(),
+            total: "100.00".to_string(),
+            ..Default::default()
+        };
+        crate::payment::new(
+            client,
+            "https://mysite.com/whooyoupaid",
+            "https://mysite.com/nooyoufailed",
+            PaymentMethod::Paypal,
+            PaymentIntent::Sale,
+            vec![Transaction {
+                amount,
+                ..Default::default()
+            }],
+        )
+    }
+
+    #[test]
+    fn test_get_token() {
+        let server = MockServer::start();
+        let token = crate::get_token_from(&server.url(), CLIENT_ID, SECRET).unwrap();
+        assert_eq!("Bearer", token.token_type);
+        let err = crate::get_token_from(&server.url(), CLIENT_ID, "wrong").unwrap_err();
+        assert_eq!(Some(401), err.status());
+    }
+
+    #[test]
+    fn test_payment_lifecycle() {
+        let server = MockServer::start();
+        let client = server.client();
+        let created = new_payment(&client).unwrap();
+        assert_eq!(Some(State::Created), created.state);
+
+        let err = crate::payment::execute(&client, &created.id, "PAYER42").unwrap_err();
+        assert_eq!(Some(400), err.status());
+
+        let redirect = server.approve_payment(&created.id, "PAYER42").unwrap();
+        assert!(redirect.starts_with("https://mysite.com/whooyoupaid?paymentId="));
+        let executed = crate::payment::execute(&client, &created.id, "PAYER42").unwrap();
+        assert_eq!(Some(State::Approved), executed.state);
+
+        let sale = &executed.transactions[0].extra["related_resources"][0]["sale"];
+        let refund_link = sale["links"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|link| link["rel"] == "refund")
+            .cloned()
+            .unwrap();
+        let refund_link = serde_json::from_value(refund_link).unwrap();
+        let refund: Value = client.follow_with(&refund_link, &json!({})).unwrap();
+        assert_eq!("completed", refund["state"]);
+        assert!(client
+            .follow_with::<_, Value>(&refund_link, &json!({}))
+            .is_err());
+
+        let list = crate::payment::list(&client).unwrap();
+        assert_eq!(1, list.count);
+    }
+
+    #[test]
+    fn test_order_lifecycle() {
+        let server = MockServer::start();
+        let client = server.client();
+        let body = json!({
+            "intent": "CAPTURE",
+            "purchase_units": [{ "amount": { "currency_code": "USD", "value": "100.00" } }],
+        });
+        let order: crate::types::Order =
+            crate::request::post_json(&client, &client.endpoint("/v2/checkout/orders"), &body)
+                .unwrap();
+        let capture = order.link(&LinkRel::Capture).unwrap();
+        let err = client
+            .follow_with::<_, Value>(capture, &json!({}))
+            .unwrap_err();
+        assert_eq!(Some(422), err.status());
+
+        server.approve_order(&order.id, "PAYER42").unwrap();
+        let captured: crate::types::Order = client.follow_with(capture, &json!({})).unwrap();
+        assert_eq!(Some(crate::types::OrderStatus::Completed), captured.status);
+    }
+
+    #[test]
+    fn test_failure_injection() {
+        let server = MockServer::start();
+        let client = server.client();
+        server.fail("POST", "/v1/payments/payment", 500, 1);
+        assert_eq!(Some(500), new_payment(&client).unwrap_err().status());
+        new_payment(&client).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_unknown_tokens() {
+        let server = MockServer::start();
+        let client = Client::new("not-a-token").with_base_url(&server.url());
+        assert_eq!(
+            Some(401),
+            crate::payment::list(&client).unwrap_err().status()
+        );
+    }
+}
@@ -1,47 +1,152 @@
+use crate::client::Client;
+use crate::credentials::Credentials;
 use crate::error::Error;
-use crate::types::ResponseAccessToken;
+use crate::metrics::TOKEN_ENDPOINT;
+use crate::transport::{ReqwestTransport, Transport};
+use crate::types::{ResponseAccessToken, Scope, Scopes};
 use base64::encode;
+use zeroize::{Zeroize, Zeroizing};
 
 /// This function uses your `client id` and `secret`, and creates a bearer token that
 /// you must use to get access to the paypal endpoints
 pub fn get_token(id: &str, secret: &str) -> Result<ResponseAccessToken, Error> {
-    let ep = crate::_make_endpoint("/v1/oauth2/token");
+    get_token_from(crate::_ADDR, id, secret)
+}
+
+/// Like `get_token`, but asks for a token granting only `scopes`, so that a leaked token can do
+/// less harm.
+///
+/// ```rust,no_run
+/// use paypal::{get_token_scoped, Scope};
+///
+/// let token = get_token_scoped("my_id", "my_secret", &[Scope::Payments]).unwrap();
+/// assert!(token.scope.contains(&Scope::Payments));
+/// ```
+pub fn get_token_scoped(
+    id: &str,
+    secret: &str,
+    scopes: &[Scope],
+) -> Result<ResponseAccessToken, Error> {
+    get_token_scoped_using(
+        &ReqwestTransport::default(),
+        crate::_ADDR,
+        id,
+        secret,
+        scopes,
+    )
+}
+
+/// Like `get_token_scoped`, but requests the token from `base_url` through `transport`, as
+/// `get_token_using` does.
+pub fn get_token_scoped_using(
+    transport: &dyn Transport,
+    base_url: &str,
+    id: &str,
+    secret: &str,
+    scopes: &[Scope],
+) -> Result<ResponseAccessToken, Error> {
+    let ep = format!("{}{}", base_url.trim_end_matches('/'), TOKEN_ENDPOINT);
+    request(transport, None, &ep, id, secret, scopes)
+}
+
+/// Like `get_token`, but requests the token from `base_url` instead of PayPal's live or sandbox
+/// API, for example from a mock server in tests.
+pub fn get_token_from(
+    base_url: &str,
+    id: &str,
+    secret: &str,
+) -> Result<ResponseAccessToken, Error> {
+    get_token_using(&ReqwestTransport::default(), base_url, id, secret)
+}
+
+/// Like `get_token_from`, but sends the request through `transport`, for example one configured
+/// with the same proxy and timeouts as the `Client` the token is for.
+pub fn get_token_using(
+    transport: &dyn Transport,
+    base_url: &str,
+    id: &str,
+    secret: &str,
+) -> Result<ResponseAccessToken, Error> {
+    get_token_scoped_using(transport, base_url, id, secret, &[])
+}
+
+// the token of a client created with `Client::from_credentials`, requested through the client so
+// that its middleware and metrics hooks see the request
+pub(crate) fn request_for(
+    client: &Client,
+    credentials: &Credentials,
+) -> Result<ResponseAccessToken, Error> {
+    request(
+        client.transport(),
+        Some(client),
+        &client.endpoint(TOKEN_ENDPOINT),
+        credentials.client_id(),
+        credentials.expose_secret(),
+        credentials.scopes(),
+    )
+}
+
+fn request(
+    transport: &dyn Transport,
+    client: Option<&Client>,
+    ep: &str,
+    id: &str,
+    secret: &str,
+    scopes: &[Scope],
+) -> Result<ResponseAccessToken, Error> {
     let auth_header = prepare_auth_header(id, secret);
     let mut headers = std::collections::HashMap::<String, String>::new();
-    headers.insert("Authorization".into(), auth_header);
+    headers.insert("Authorization".into(), String::clone(&auth_header));
     headers.insert(
         "content-type".into(),
         "application/x-www-form-urlencoded".into(),
     );
     let mut form = std::collections::HashMap::new();
     form.insert("grant_type".into(), "client_credentials".into());
-    crate::request::post(&ep, &headers, &form)
+    if !scopes.is_empty() {
+        let scopes: Scopes = scopes.iter().cloned().collect();
+        form.insert("scope".into(), scopes.to_string());
+    }
+    // the token may have been issued as soon as the request was sent
+    let sent_at = chrono::Utc::now();
+    let response = crate::request::post(transport, client, ep, &headers, &form);
+    headers.values_mut().for_each(Zeroize::zeroize);
+    response.map(|token| ResponseAccessToken {
+        received_at: Some(sent_at),
+        ..token
+    })
 }
 
-fn prepare_auth_header(id: &str, secret: &str) -> String {
-    format!("Basic {}", base64_credentials(id, secret))
+fn prepare_auth_header(id: &str, secret: &str) -> Zeroizing<String> {
+    let credentials = base64_credentials(id, secret);
+    let mut header = Zeroizing::new(String::with_capacity(6 + credentials.len()));
+    header.push_str("Basic ");
+    header.push_str(&credentials);
+    header
 }
 
-fn base64_credentials(id: &str, secret: &str) -> String {
-    let mut key = String::from(id);
-    key.push_str(":");
+fn base64_credentials(id: &str, secret: &str) -> Zeroizing<String> {
+    let mut key = Zeroizing::new(String::from(id));
+    key.push(':');
     key.push_str(secret);
-    encode(&key)
+    Zeroizing::new(encode(key.as_bytes()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cassette::Cassette;
     use lazy_static::lazy_static;
 
     lazy_static! {
         static ref CLIENT_ID: String = {
             dotenv::dotenv().ok();
-            std::env::var("CLIENT_ID").expect("`CLIENT_ID` env parameter to be set")
+            // only used when recording the cassettes, replaying needs no credentials
+            std::env::var("CLIENT_ID").unwrap_or_default()
         };
         static ref SECRET: String = {
             dotenv::dotenv().ok();
-            std::env::var("SECRET").expect("`SECRET` env parameter to be set")
+            std::env::var("SECRET").unwrap_or_default()
         };
     }
 
@@ -49,7 +154,7 @@ mod tests {
     fn test_base64_credentials() {
         assert_eq!(
             "bmlrb3M6cGFzc3dvcmQ=",
-            base64_credentials("nikos", "password")
+            *base64_credentials("nikos", "password")
         )
     }
 
@@ -57,12 +162,17 @@ mod tests {
     fn test_prepare_auth_header() {
         assert_eq!(
             "Basic bmlrb3M6cGFzc3dvcmQ=",
-            prepare_auth_header("nikos", "password")
+            *prepare_auth_header("nikos", "password")
         )
     }
 
     #[test]
     fn test_get_token() {
-        get_token(&CLIENT_ID, &SECRET).unwrap();
+        Cassette::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/get_token.json"
+        ))
+        .run(|| get_token(&CLIENT_ID, &SECRET))
+        .unwrap();
     }
 }
@@ -0,0 +1,451 @@
+use crate::client::Client;
+use crate::credentials::{AccessToken, Credentials};
+use crate::error::Error;
+use crate::types::{ResponseAccessToken, Scopes};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use zeroize::Zeroizing;
+
+// a token this close to expiring is refreshed rather than used, so that it does not expire in
+// flight
+const REFRESH_MARGIN: i64 = 60;
+
+/// An access token together with the moment it expires, as kept in a `TokenStore`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoredToken {
+    /// The token itself.
+    pub access_token: AccessToken,
+    /// When PayPal stops accepting the token.
+    pub expires_at: DateTime<Utc>,
+    /// What the token grants access to.
+    #[serde(default)]
+    pub scope: Scopes,
+}
+
+impl StoredToken {
+    /// The token PayPal answered with. It expires `expires_in` seconds after `received_at`, or
+    /// from now if that is not known.
+    pub fn from_response(response: &ResponseAccessToken) -> Self {
+        let expires_at = response
+            .expires_at()
+            .unwrap_or_else(|| Utc::now() + chrono::Duration::seconds(response.expires_in));
+        Self {
+            access_token: response.access_token.clone(),
+            expires_at,
+            scope: response.scope.clone(),
+        }
+    }
+
+    /// Whether the token expires within `seconds` from now, or has already expired.
+    pub fn expires_within(&self, seconds: i64) -> bool {
+        self.expires_at <= Utc::now() + chrono::Duration::seconds(seconds)
+    }
+}
+
+/// Where a `Client` created with `Client::from_credentials` keeps its access token, so that
+/// processes sharing a store share one token instead of each requesting their own. Tokens are
+/// kept under a key naming the app, the API and any scopes they are for.
+///
+/// Implement it to keep tokens elsewhere, for example in Redis:
+///
+/// ```rust
+/// use paypal::error::Error;
+/// use paypal::token_store::{StoredToken, TokenStore};
+///
+/// struct Redis;
+///
+/// impl TokenStore for Redis {
+///     fn load(&self, key: &str) -> Result<Option<StoredToken>, Error> {
+///         // GET key, then deserialize the JSON with serde_json
+///         Ok(None)
+///     }
+///
+///     fn save(&self, key: &str, token: &StoredToken) -> Result<(), Error> {
+///         // SET key to the token serialized with serde_json, expiring at `token.expires_at`
+///         Ok(())
+///     }
+/// }
+///
+/// let credentials = paypal::Credentials::new("my_id", "my_secret".into());
+/// let client = paypal::Client::from_credentials(credentials).with_token_store(Redis);
+/// ```
+pub trait TokenStore: Send + Sync {
+    /// The token last saved under `key`, if any. It may have expired.
+    fn load(&self, key: &str) -> Result<Option<StoredToken>, Error>;
+
+    /// Saves `token` under `key`, replacing any token saved before.
+    fn save(&self, key: &str, token: &StoredToken) -> Result<(), Error>;
+}
+
+impl<S: TokenStore + ?Sized> TokenStore for Arc<S> {
+    fn load(&self, key: &str) -> Result<Option<StoredToken>, Error> {
+        (**self).load(key)
+    }
+
+    fn save(&self, key: &str, token: &StoredToken) -> Result<(), Error> {
+        (**self).save(key, token)
+    }
+}
+
+/// Keeps tokens in memory, shared by the clients using the same store. The default store of
+/// `Client::from_credentials`.
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore(Mutex<HashMap<String, StoredToken>>);
+
+impl MemoryTokenStore {
+    /// An empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self, key: &str) -> Result<Option<StoredToken>, Error> {
+        Ok(self.0.lock().unwrap().get(key).cloned())
+    }
+
+    fn save(&self, key: &str, token: &StoredToken) -> Result<(), Error> {
+        self.0.lock().unwrap().insert(key.into(), token.clone());
+        Ok(())
+    }
+}
+
+/// Keeps tokens in a JSON file, shared by the processes on a machine. The file holds live
+/// tokens, so on Unix it is created readable by its owner only. Saves hold an advisory lock on a
+/// `.lock` file next to it, so that concurrent saves do not drop each other's tokens.
+///
+/// ```rust,no_run
+/// use paypal::token_store::FileTokenStore;
+/// use paypal::{Client, Credentials};
+///
+/// let client = Client::from_credentials(Credentials::from_env().unwrap())
+///     .with_token_store(FileTokenStore::new("/var/cache/myapp/paypal-tokens.json"));
+/// ```
+#[derive(Debug)]
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+impl FileTokenStore {
+    /// A store keeping its tokens in the file at `path`, which is created on the first save.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn read(&self) -> Result<HashMap<String, StoredToken>, Error> {
+        let file = match std::fs::read_to_string(&self.path) {
+            Ok(file) => Zeroizing::new(file),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(err) => return Err(self.error("read", err)),
+        };
+        serde_json::from_str(&file).map_err(Error::from)
+    }
+
+    // written to a temporary file first, so that readers never see half a file
+    fn write(&self, tokens: &HashMap<String, StoredToken>) -> Result<(), Error> {
+        let json = Zeroizing::new(serde_json::to_vec(tokens)?);
+        let temporary = self.sibling(&format!(".{}.tmp", std::process::id()));
+        let written = private_file(&temporary).and_then(|mut file| {
+            std::io::Write::write_all(&mut file, &json)?;
+            std::fs::rename(&temporary, &self.path)
+        });
+        written.map_err(|err| self.error("write", err))
+    }
+
+    // held until the returned file is dropped; the token file itself cannot be locked, as every
+    // save replaces it
+    fn lock(&self) -> Result<std::fs::File, Error> {
+        let lock = private_file(&self.sibling(".lock"))
+            .and_then(|file| fs2::FileExt::lock_exclusive(&file).map(|()| file));
+        lock.map_err(|err| self.error("lock", err))
+    }
+
+    fn sibling(&self, suffix: &str) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(suffix);
+        PathBuf::from(path)
+    }
+
+    fn error(&self, action: &str, err: std::io::Error) -> Error {
+        Error::new(format!(
+            "cannot {} {}: {}",
+            action,
+            self.path.display(),
+            err
+        ))
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self, key: &str) -> Result<Option<StoredToken>, Error> {
+        Ok(self.read()?.remove(key))
+    }
+
+    fn save(&self, key: &str, token: &StoredToken) -> Result<(), Error> {
+        let _lock = self.lock()?;
+        // a broken file is replaced rather than blocking every save
+        let mut tokens = self.read().unwrap_or_default();
+        tokens.retain(|_, token| !token.expires_within(0));
+        tokens.insert(key.into(), token.clone());
+        self.write(&tokens)
+    }
+}
+
+fn private_file(path: &Path) -> std::io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+// the token of a client created with `Client::from_credentials`, requested when first needed and
+// again shortly before it expires
+pub(crate) struct ManagedToken {
+    credentials: Credentials,
+    store: Arc<dyn TokenStore>,
+    current: Mutex<Option<StoredToken>>,
+}
+
+impl ManagedToken {
+    pub(crate) fn new(credentials: Credentials, store: Arc<dyn TokenStore>) -> Self {
+        Self {
+            credentials,
+            store,
+            current: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn credentials(&self) -> &Credentials {
+        &self.credentials
+    }
+
+    // the scopes of the token in use, unknown until one was requested, or when it was stored
+    // without them
+    pub(crate) fn scopes(&self) -> Option<Scopes> {
+        let current = self.current.lock().unwrap();
+        current
+            .as_ref()
+            .map(|token| token.scope.clone())
+            .filter(|scopes| !scopes.is_empty())
+    }
+
+    fn key(&self, client: &Client) -> String {
+        let mut key = format!("{}@{}", self.credentials.client_id(), client.endpoint(""));
+        for scope in self.credentials.scopes() {
+            key.push(' ');
+            key.push_str(scope.as_str());
+        }
+        key
+    }
+
+    pub(crate) fn get(&self, client: &Client) -> Result<AccessToken, Error> {
+        self.token(client, None)
+    }
+
+    // after PayPal refused `rejected`, another token: the one a clone of the client or another
+    // process sharing the store already got in its place, or a new one
+    pub(crate) fn replace(&self, client: &Client, rejected: &str) -> Result<AccessToken, Error> {
+        self.token(client, Some(rejected))
+    }
+
+    // the lock is held while requesting a token, so that clones of a client refresh it once.
+    // A failing store is logged and worked around, as it only costs an extra token request.
+    fn token(&self, client: &Client, rejected: Option<&str>) -> Result<AccessToken, Error> {
+        let usable = |token: &StoredToken| {
+            !token.expires_within(REFRESH_MARGIN)
+                && Some(token.access_token.expose_secret()) != rejected
+        };
+        let mut current = self.current.lock().unwrap();
+        if let Some(token) = current.as_ref().filter(|token| usable(token)) {
+            return Ok(token.access_token.clone());
+        }
+        let key = self.key(client);
+        let stored = self.store.load(&key).unwrap_or_else(|err| {
+            tracing::warn!(error = %err, "cannot load the paypal token");
+            None
+        });
+        let token = match stored.filter(usable) {
+            Some(token) => token,
+            None => {
+                let response = crate::token::request_for(client, &self.credentials)?;
+                let token = StoredToken::from_response(&response);
+                if let Err(err) = self.store.save(&key, &token) {
+                    tracing::warn!(error = %err, "cannot save the paypal token");
+                }
+                token
+            }
+        };
+        *current = Some(token.clone());
+        Ok(token.access_token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpRequest, HttpResponse, Transport};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // hands out numbered tokens granting `scope`, and answers every other call with an empty
+    // list, or refuses it when `forbidden` or not made with the last token handed out
+    #[derive(Clone, Default)]
+    struct Paypal(Arc<AtomicUsize>, bool, &'static str);
+
+    impl Transport for Paypal {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+            let body = if request.url.ends_with("/v1/oauth2/token") {
+                let issued = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+                serde_json::json!({
+                    "scope": self.2,
+                    "nonce": "2020-04-03T15:35:36ZaYZlGvEkV4yVSz8g6bAKFoGSEzuy3CQcz3ljhibkOHg",
+                    "access_token": format!("A21AA{}", issued),
+                    "token_type": "Bearer",
+                    "app_id": "APP-80W284485P519543T",
+                    "expires_in": 32400
+                })
+            } else if self.1 {
+                return Ok(HttpResponse {
+                    status: 403,
+                    headers: Default::default(),
+                    body: br#"{"name":"NOT_AUTHORIZED"}"#.to_vec(),
+                });
+            } else if request.headers["Authorization"]
+                != format!("Bearer A21AA{}", self.0.load(Ordering::SeqCst))
+            {
+                return Ok(HttpResponse {
+                    status: 401,
+                    headers: Default::default(),
+                    body: br#"{"error":"invalid_token"}"#.to_vec(),
+                });
+            } else {
+                serde_json::json!({ "payments": [], "count": 0 })
+            };
+            Ok(HttpResponse {
+                status: 200,
+                headers: Default::default(),
+                body: body.to_string().into_bytes(),
+            })
+        }
+    }
+
+    fn client(paypal: &Paypal, store: Arc<MemoryTokenStore>) -> Client {
+        Client::from_credentials(Credentials::new("my_id", "my_secret".into()))
+            .with_transport(paypal.clone())
+            .with_base_url("https://api.sandbox.paypal.com")
+            .with_token_store(store)
+    }
+
+    #[test]
+    fn test_clients_share_the_stored_token() {
+        let paypal = Paypal::default();
+        let store = Arc::new(MemoryTokenStore::new());
+        crate::payment::list(&client(&paypal, store.clone())).unwrap();
+        crate::payment::list(&client(&paypal, store.clone())).unwrap();
+        assert_eq!(1, paypal.0.load(Ordering::SeqCst));
+
+        let key = "my_id@https://api.sandbox.paypal.com";
+        let mut token = store.load(key).unwrap().unwrap();
+        token.expires_at = Utc::now();
+        store.save(key, &token).unwrap();
+        crate::payment::list(&client(&paypal, store.clone())).unwrap();
+        assert_eq!(2, paypal.0.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_rejected_token_is_replaced() {
+        let paypal = Paypal::default();
+        let store = Arc::new(MemoryTokenStore::new());
+        let key = "my_id@https://api.sandbox.paypal.com";
+        let revoked = StoredToken {
+            access_token: AccessToken::new("REVOKED".into()),
+            expires_at: Utc::now() + chrono::Duration::hours(9),
+            scope: Scopes::default(),
+        };
+        store.save(key, &revoked).unwrap();
+        crate::payment::list(&client(&paypal, store.clone())).unwrap();
+        assert_eq!(1, paypal.0.load(Ordering::SeqCst));
+        let stored = store.load(key).unwrap().unwrap();
+        assert_eq!("A21AA1", stored.access_token.expose_secret());
+
+        // a client whose token was replaced by another sharing the store takes that one
+        let stale = client(&paypal, store.clone());
+        crate::payment::list(&stale).unwrap();
+        store.save(key, &revoked).unwrap();
+        crate::payment::list(&client(&paypal, store.clone())).unwrap();
+        assert_eq!(2, paypal.0.load(Ordering::SeqCst));
+        crate::payment::list(&stale).unwrap();
+        assert_eq!(2, paypal.0.load(Ordering::SeqCst));
+
+        // a token the caller gave is not replaced
+        let bearer = Client::new("REVOKED")
+            .with_transport(paypal.clone())
+            .with_base_url("https://api.sandbox.paypal.com");
+        let err = crate::payment::list(&bearer).unwrap_err();
+        assert_eq!(Some(401), err.status());
+        assert_eq!(2, paypal.0.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_missing_scope() {
+        let invoicing = "https://uri.paypal.com/services/invoicing";
+        let paypal = Paypal(Default::default(), true, invoicing);
+        let err = crate::payment::list(&client(&paypal, Default::default())).unwrap_err();
+        assert_eq!(Some(&crate::Scope::Payments), err.missing_scope());
+        assert!(err
+            .message()
+            .contains("lacks the `https://api.paypal.com/v1/payments/.*` or"));
+
+        // refused for some other reason, as the token has a scope the endpoint accepts
+        let payment = "https://uri.paypal.com/services/payments/payment";
+        let paypal = Paypal(Default::default(), true, payment);
+        let err = crate::payment::list(&client(&paypal, Default::default())).unwrap_err();
+        assert_eq!(None, err.missing_scope());
+        assert_eq!(r#"{"name":"NOT_AUTHORIZED"}"#, err.message());
+    }
+
+    #[test]
+    fn test_file_store() {
+        let path = std::env::temp_dir().join(format!("paypal-tokens-{}.json", std::process::id()));
+        let store = FileTokenStore::new(&path);
+        assert_eq!(None, store.load("my_id").unwrap());
+        let token = StoredToken {
+            access_token: AccessToken::new("A21AAF".into()),
+            expires_at: Utc::now() + chrono::Duration::hours(9),
+            scope: Scopes::default(),
+        };
+        store.save("my_id", &token).unwrap();
+        let loaded = FileTokenStore::new(&path).load("my_id").unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(store.sibling(".lock")).unwrap();
+        assert_eq!(Some(token), loaded);
+    }
+
+    #[test]
+    fn test_concurrent_saves_keep_every_token() {
+        let path = std::env::temp_dir().join(format!("paypal-shared-{}.json", std::process::id()));
+        let saves = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let token = StoredToken {
+                        access_token: AccessToken::new(format!("A21AAF{}", i)),
+                        expires_at: Utc::now() + chrono::Duration::hours(9),
+                        scope: Scopes::default(),
+                    };
+                    FileTokenStore::new(path).save(&format!("app{}", i), &token)
+                })
+            })
+            .collect::<Vec<_>>();
+        for save in saves {
+            save.join().unwrap().unwrap();
+        }
+        let tokens = FileTokenStore::new(&path).read().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(FileTokenStore::new(&path).sibling(".lock")).unwrap();
+        assert_eq!(8, tokens.len());
+    }
+}
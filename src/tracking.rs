@@ -0,0 +1,128 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::request::Resp;
+use crate::types::{Order, OrderTracker, RequestTrackersBatch, Tracker, TrackersBatchResponse};
+
+/// Adds tracking information for one or more PayPal transactions. PayPal accepts up to 20
+/// trackers per call; trackers it rejects are reported in `errors` rather than failing the call.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, tracking, Client};
+/// use paypal::{Carrier, Tracker, TrackerStatus};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let tracker = Tracker {
+///     transaction_id: "8MC585209K746392H".to_string(),
+///     tracking_number: Some("443844607820".to_string()),
+///     status: TrackerStatus::Shipped,
+///     carrier: Some(Carrier::Fedex),
+///     carrier_name_other: None,
+///     shipment_date: None,
+///     notify_buyer: Some(true),
+///     links: Vec::new(),
+///     extra: Default::default(),
+/// };
+/// let added = tracking::add(&Client::new(&token.access_token), &[tracker]).unwrap();
+/// ```
+pub fn add(client: &Client, trackers: &[Tracker]) -> Resp<TrackersBatchResponse> {
+    crate::request::post_json(
+        client,
+        &client.endpoint("/v1/shipping/trackers-batch"),
+        &RequestTrackersBatch { trackers },
+    )
+}
+
+/// Shows tracking information for the shipment identified by a transaction and tracking number.
+pub fn get(client: &Client, transaction_id: &str, tracking_number: &str) -> Resp<Tracker> {
+    crate::request::get(
+        client,
+        &client.endpoint(&tracker_path(transaction_id, tracking_number)?),
+    )
+}
+
+/// Replaces the tracking information of the shipment identified by a transaction and tracking
+/// number, for example to move it from `Shipped` to `Delivered`.
+pub fn update(
+    client: &Client,
+    transaction_id: &str,
+    tracking_number: &str,
+    tracker: &Tracker,
+) -> Resp<()> {
+    crate::request::put_json(
+        client,
+        &client.endpoint(&tracker_path(transaction_id, tracking_number)?),
+        tracker,
+    )
+}
+
+/// Adds tracking information for a captured v2 order and returns the updated order.
+pub fn add_to_order(client: &Client, order_id: &str, tracker: &OrderTracker) -> Resp<Order> {
+    crate::request::post_json(
+        client,
+        &client.endpoint(&format!("/v2/checkout/orders/{}/track", order_id)),
+        tracker,
+    )
+}
+
+// PayPal identifies a tracker by its transaction id and tracking number joined with a dash
+fn tracker_path(transaction_id: &str, tracking_number: &str) -> Resp<String> {
+    Ok(format!(
+        "/v1/shipping/trackers/{}-{}",
+        segment(transaction_id)?,
+        segment(tracking_number)?
+    ))
+}
+
+// percent-encoded like `partner_referral::find_merchant` encodes its query, except for spaces,
+// which a query writes as `+` but a path would read as a plus sign
+fn segment(value: &str) -> Resp<String> {
+    let encoded =
+        serde_urlencoded::to_string([("", value)]).map_err(|err| Error::new(err.to_string()))?;
+    Ok(encoded.trim_start_matches('=').replace('+', "%20"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Carrier, TrackerStatus};
+
+    #[test]
+    fn test_tracker_path() {
+        assert_eq!(
+            "/v1/shipping/trackers/8MC585209K746392H-443844607820",
+            tracker_path("8MC585209K746392H", "443844607820").unwrap()
+        );
+        assert_eq!(
+            "/v1/shipping/trackers/8MC585209K746392H-1Z%2F99%3Fa%20b%2Bc%23",
+            tracker_path("8MC585209K746392H", "1Z/99?a b+c#").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_carrier_round_trip() {
+        let known: Carrier = serde_json::from_str("\"DHL_API\"").unwrap();
+        assert_eq!(Carrier::DhlApi, known);
+        let other: Carrier = serde_json::from_str("\"POSTI\"").unwrap();
+        assert_eq!(Carrier::Other("POSTI".to_string()), other);
+        assert_eq!("\"POSTI\"", serde_json::to_string(&other).unwrap());
+    }
+
+    #[test]
+    fn test_tracker_serialization() {
+        let tracker = Tracker {
+            transaction_id: "8MC585209K746392H".to_string(),
+            tracking_number: Some("443844607820".to_string()),
+            status: TrackerStatus::Shipped,
+            carrier: Some(Carrier::Fedex),
+            carrier_name_other: None,
+            shipment_date: None,
+            notify_buyer: None,
+            links: Vec::new(),
+            extra: Default::default(),
+        };
+        assert_eq!(
+            r#"{"transaction_id":"8MC585209K746392H","tracking_number":"443844607820","status":"SHIPPED","carrier":"FEDEX"}"#,
+            serde_json::to_string(&tracker).unwrap()
+        )
+    }
+}
@@ -0,0 +1,127 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::pagination::{Cursor, Page, Paginator};
+use crate::types::{TransactionDetails, TransactionSearch, TransactionSearchResponse};
+
+/// Searches the transactions of the account, such as sales, captures and refunds, fetching them
+/// a page at a time. Transactions show up in the search up to three hours after they happen.
+///
+/// ```rust,no_run
+/// use chrono::{Duration, Utc};
+/// use paypal::{get_token, transaction, Client, TransactionSearch};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let client = Client::new(&token.access_token);
+/// let search = TransactionSearch::new(Utc::now() - Duration::days(7), Utc::now());
+/// for found in transaction::search(&client, &search).with_page_size(100) {
+///     println!("{}", found.unwrap().transaction_info.transaction_id);
+/// }
+/// ```
+pub fn search(client: &Client, search: &TransactionSearch) -> Paginator<TransactionDetails> {
+    let client = client.clone();
+    let search = search.clone();
+    Paginator::new(move |request| {
+        let mut query = vec![
+            ("start_date", date(&search.start_date)),
+            ("end_date", date(&search.end_date)),
+            ("fields", "transaction_info".to_string()),
+        ];
+        if let Some(transaction_id) = &search.transaction_id {
+            query.push(("transaction_id", transaction_id.clone()));
+        }
+        if let Some(status) = &search.transaction_status {
+            query.push(("transaction_status", status.to_string()));
+        }
+        if let Some(page_size) = request.page_size {
+            query.push(("page_size", page_size.to_string()));
+        }
+        if let Some(Cursor::Page(page)) = &request.cursor {
+            query.push(("page", page.to_string()));
+        }
+        let query =
+            serde_urlencoded::to_string(&query).map_err(|err| Error::new(err.to_string()))?;
+        let found: TransactionSearchResponse = crate::request::get(
+            &client,
+            &client.endpoint(&format!("/v1/reporting/transactions?{}", query)),
+        )?;
+        Ok(Page::numbered(
+            found.transaction_details,
+            found.page,
+            found.total_pages,
+        ))
+    })
+}
+
+// PayPal wants the offset without a colon, as in 2014-07-01T00:00:00+0000
+fn date(date: &chrono::DateTime<chrono::Utc>) -> String {
+    date.format("%Y-%m-%dT%H:%M:%S%z").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpRequest, HttpResponse, Transport};
+    use crate::types::TransactionStatus;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    // two pages of one transaction each, remembering the urls it was asked for
+    struct Paypal(Arc<Mutex<Vec<String>>>);
+
+    impl Transport for Paypal {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+            self.0.lock().unwrap().push(request.url.clone());
+            let page = if request.url.contains("page=2") { 2 } else { 1 };
+            let body = json!({
+                "transaction_details": [{
+                    "transaction_info": {
+                        "transaction_id": format!("TXN{}", page),
+                        "transaction_event_code": "T0006",
+                        "transaction_amount": { "currency_code": "USD", "value": "10.00" },
+                        "transaction_status": "S",
+                    },
+                }],
+                "account_number": "XZXSPECPDZHZU",
+                "page": page,
+                "total_items": 2,
+                "total_pages": 2,
+            });
+            Ok(HttpResponse {
+                status: 200,
+                headers: Default::default(),
+                body: body.to_string().into_bytes(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let urls = Arc::new(Mutex::new(Vec::new()));
+        let client = Client::new("token")
+            .with_base_url("https://example.com")
+            .with_transport(Paypal(urls.clone()));
+        let search = TransactionSearch::new(
+            "2024-07-01T00:00:00Z".parse().unwrap(),
+            "2024-07-31T23:59:59Z".parse().unwrap(),
+        );
+        let found: Vec<_> = super::search(&client, &search)
+            .with_page_size(1)
+            .map(|found| found.unwrap().transaction_info)
+            .collect();
+        assert_eq!(2, found.len());
+        assert_eq!("TXN1", found[0].transaction_id);
+        assert_eq!("TXN2", found[1].transaction_id);
+        assert_eq!(
+            Some(TransactionStatus::Success),
+            found[0].transaction_status
+        );
+        let urls = urls.lock().unwrap();
+        assert_eq!(
+            "https://example.com/v1/reporting/transactions\
+             ?start_date=2024-07-01T00%3A00%3A00%2B0000&end_date=2024-07-31T23%3A59%3A59%2B0000\
+             &fields=transaction_info&page_size=1",
+            urls[0]
+        );
+        assert!(urls[1].ends_with("&page_size=1&page=2"));
+    }
+}
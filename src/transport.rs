@@ -0,0 +1,487 @@
+use crate::error::Error;
+use crate::redact;
+use reqwest::header::{HeaderMap, USER_AGENT};
+use reqwest::Method;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use zeroize::Zeroize;
+
+/// An HTTP request, as handed to a `Transport`. Its `Debug` output redacts credentials, and its
+/// header values are zeroized when it is dropped.
+#[derive(Clone)]
+pub struct HttpRequest {
+    /// The HTTP method.
+    pub method: Method,
+    /// The complete url, including the base url and any query string.
+    pub url: String,
+    /// The headers to send, including `Authorization`.
+    pub headers: HashMap<String, String>,
+    /// The body to send, if any.
+    pub body: Option<Vec<u8>>,
+    /// How long the whole exchange may take, overriding the transport's own timeout.
+    pub timeout: Option<Duration>,
+}
+
+/// An HTTP response, as returned by a `Transport`. Its `Debug` output redacts tokens.
+#[derive(Clone)]
+pub struct HttpResponse {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The response headers. Names are lowercase.
+    pub headers: HashMap<String, String>,
+    /// The response body.
+    pub body: Vec<u8>,
+}
+
+impl Drop for HttpRequest {
+    fn drop(&mut self) {
+        self.headers.values_mut().for_each(Zeroize::zeroize);
+    }
+}
+
+impl fmt::Debug for HttpRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpRequest")
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("headers", &redact::headers(&self.headers))
+            .field("body", &self.body.as_deref().map(redact::body))
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl fmt::Debug for HttpResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpResponse")
+            .field("status", &self.status)
+            .field("headers", &redact::headers(&self.headers))
+            .field("body", &redact::body(&self.body))
+            .finish()
+    }
+}
+
+/// Sends the HTTP requests of a `Client`. Implement it to reuse an existing HTTP stack, add
+/// proxies or mTLS, or to answer requests with canned responses in unit tests.
+///
+/// A transport only moves bytes: a response with an error status is still an `Ok` response.
+/// Return an `Err` only when no response was received at all.
+///
+/// ```rust
+/// use paypal::transport::{HttpRequest, HttpResponse, Transport};
+/// use paypal::{payment, Client};
+///
+/// struct NoPayments;
+///
+/// impl Transport for NoPayments {
+///     fn send(&self, _: &HttpRequest) -> Result<HttpResponse, paypal::error::Error> {
+///         Ok(HttpResponse {
+///             status: 200,
+///             headers: Default::default(),
+///             body: br#"{"payments": [], "count": 0}"#.to_vec(),
+///         })
+///     }
+/// }
+///
+/// let client = Client::new("token").with_transport(NoPayments);
+/// assert_eq!(0, payment::list(&client).unwrap().count);
+/// ```
+pub trait Transport: Send + Sync {
+    /// Sends `request` and returns the response PayPal answered with.
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error>;
+}
+
+/// The default transport, sending requests with a `reqwest::Client`. Clones share the
+/// connection pool, and so do all clients using `ReqwestTransport::default()`.
+///
+/// A request that times out is aborted, so it is never completed after the caller was told it
+/// timed out. PayPal may still have processed it before the connection closed, so retry payment
+/// calls with the same `PayPal-Request-Id` header rather than blindly.
+///
+/// ```rust,no_run
+/// use paypal::transport::ReqwestTransport;
+/// use paypal::Client;
+/// use std::time::Duration;
+///
+/// let transport = ReqwestTransport::builder()
+///     .connect_timeout(Duration::from_secs(5))
+///     .read_timeout(Duration::from_secs(20))
+///     .timeout(Duration::from_secs(30))
+///     .proxy(reqwest::Proxy::https("http://proxy.internal:3128").unwrap())
+///     .user_agent("my-shop/1.0")
+///     .build()
+///     .unwrap();
+/// let client = Client::new("token").with_transport(transport);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+    // the total timeout, when no per-call one is given
+    timeout: Option<Duration>,
+    // how clients with shorter read timeouts are built, unless the client was handed to `new`
+    builder: Option<ReqwestTransportBuilder>,
+    // the clients built for shorter read timeouts, at most one per `READ_TIMEOUT_STEPS`
+    shorter: Arc<Mutex<HashMap<Duration, reqwest::Client>>>,
+}
+
+// the read timeouts short deadlines are rounded down to, so that only a few clients are ever
+// built, each with its own runtime thread and connection pool
+const READ_TIMEOUT_STEPS: &[Duration] = &[
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+    Duration::from_secs(30),
+];
+
+impl ReqwestTransport {
+    /// A transport sending requests with the given, already configured, `reqwest::Client`. Its
+    /// own timeout bounds every wait for data, and total timeouts given with
+    /// `Client::with_timeout` are checked whenever data arrives, so a stalled read may overshoot
+    /// them by up to that timeout.
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            timeout: None,
+            builder: None,
+            shorter: Default::default(),
+        }
+    }
+
+    /// Starts configuring a transport with its own connection pool.
+    pub fn builder() -> ReqwestTransportBuilder {
+        ReqwestTransportBuilder::default()
+    }
+
+    // a client whose read timeout is no longer than `deadline`, so that a stalled read cannot
+    // outlast it by much: the reqwest timeout is per client, so shorter ones come from a small
+    // set of clients built from the same settings
+    fn client_for(&self, deadline: Option<Duration>) -> Result<reqwest::Client, Error> {
+        let (builder, deadline) = match (&self.builder, deadline) {
+            (Some(builder), Some(deadline)) => (builder, deadline),
+            _ => return Ok(self.client.clone()),
+        };
+        if builder.read_timeout.is_some_and(|read| read <= deadline) {
+            return Ok(self.client.clone());
+        }
+        let step = READ_TIMEOUT_STEPS
+            .iter()
+            .rev()
+            .find(|step| **step <= deadline)
+            .unwrap_or(&READ_TIMEOUT_STEPS[0]);
+        let mut clients = self.shorter.lock().unwrap();
+        if let Some(client) = clients.get(step) {
+            return Ok(client.clone());
+        }
+        let client = builder.clone().read_timeout(*step).reqwest_client()?;
+        clients.insert(*step, client.clone());
+        Ok(client)
+    }
+
+    // dropping the response before it is read to the end closes the connection, aborting the
+    // request
+    fn exchange(
+        client: &reqwest::Client,
+        request: &HttpRequest,
+        deadline: Option<Duration>,
+    ) -> Result<HttpResponse, Error> {
+        let started = Instant::now();
+        let check = || match deadline {
+            Some(deadline) if started.elapsed() > deadline => {
+                Err(Error::timed_out(&request.url, deadline))
+            }
+            _ => Ok(()),
+        };
+        let mut builder = client
+            .request(request.method.clone(), &request.url)
+            .headers(build_headers(&request.headers)?);
+        if let Some(body) = &request.body {
+            builder = builder.body(body.clone());
+        }
+        let mut response = builder.send()?;
+        check()?;
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.as_str().into(), value.to_str().ok()?.into())))
+            .collect();
+        let mut body = Vec::new();
+        let mut chunk = [0; 16 * 1024];
+        loop {
+            let read = response.read(&mut chunk).map_err(|err| match err.kind() {
+                std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => {
+                    Error::timed_out(&request.url, started.elapsed())
+                }
+                _ => Error::new(format!("cannot read the response: {}", err)),
+            })?;
+            check()?;
+            if read == 0 {
+                break;
+            }
+            body.extend_from_slice(&chunk[..read]);
+        }
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            headers,
+            body,
+        })
+    }
+}
+
+impl Default for ReqwestTransport {
+    fn default() -> Self {
+        static SHARED: OnceLock<ReqwestTransport> = OnceLock::new();
+        SHARED
+            .get_or_init(|| {
+                ReqwestTransport::builder()
+                    .build()
+                    .expect("the default http client")
+            })
+            .clone()
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+        let deadline = request.timeout.or(self.timeout);
+        Self::exchange(&self.client_for(deadline)?, request, deadline)
+    }
+}
+
+/// Configures a `ReqwestTransport`. See `ReqwestTransport::builder`.
+#[derive(Clone, Debug)]
+pub struct ReqwestTransportBuilder {
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxy: Option<reqwest::Proxy>,
+    user_agent: String,
+}
+
+impl Default for ReqwestTransportBuilder {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Some(Duration::from_secs(10)),
+            read_timeout: Some(Duration::from_secs(30)),
+            timeout: Some(Duration::from_secs(60)),
+            proxy: None,
+            user_agent: concat!("paypal-rust/", env!("CARGO_PKG_VERSION")).into(),
+        }
+    }
+}
+
+impl ReqwestTransportBuilder {
+    /// How long to wait for a connection to PayPal. Defaults to 10 seconds, `None` waits forever.
+    pub fn connect_timeout<T: Into<Option<Duration>>>(mut self, timeout: T) -> Self {
+        self.connect_timeout = timeout.into();
+        self
+    }
+
+    /// How long to wait for the response once the request is sent, and then for each read of the
+    /// response body. Defaults to 30 seconds, `None` waits forever.
+    pub fn read_timeout<T: Into<Option<Duration>>>(mut self, timeout: T) -> Self {
+        self.read_timeout = timeout.into();
+        self
+    }
+
+    /// How long the whole exchange may take, from sending the request to reading the last byte
+    /// of the response. Defaults to 60 seconds, `None` waits forever. `Client::with_timeout`
+    /// overrides it per call. Waits for data are cut short to keep within it.
+    pub fn timeout<T: Into<Option<Duration>>>(mut self, timeout: T) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+
+    /// Sends every request through `proxy`.
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// The `User-Agent` header to send. Defaults to `paypal-rust/<version>`.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Builds the transport, failing when the user agent is not a valid header value or the
+    /// TLS backend cannot be initialized.
+    pub fn build(self) -> Result<ReqwestTransport, Error> {
+        Ok(ReqwestTransport {
+            client: self.reqwest_client()?,
+            timeout: self.timeout,
+            builder: Some(self),
+            shorter: Default::default(),
+        })
+    }
+
+    fn reqwest_client(&self) -> Result<reqwest::Client, Error> {
+        let mut headers = HeaderMap::new();
+        let user_agent = self
+            .user_agent
+            .parse()
+            .map_err(|_| Error::new(format!("invalid user agent `{}`", self.user_agent)))?;
+        headers.insert(USER_AGENT, user_agent);
+        let mut builder = reqwest::Client::builder()
+            .default_headers(headers)
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.read_timeout);
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        Ok(builder.build()?)
+    }
+}
+
+fn build_headers(map: &HashMap<String, String>) -> Result<HeaderMap, Error> {
+    let mut header_map = HeaderMap::new();
+    for (k, v) in map {
+        let name = reqwest::header::HeaderName::from_str(k.as_str())
+            .map_err(|_| Error::new(format!("invalid header name `{}`", k)))?;
+        let value = v
+            .parse()
+            .map_err(|_| Error::new(format!("invalid value for header `{}`", k)))?;
+        header_map.insert(name, value);
+    }
+    Ok(header_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::Client;
+    use std::sync::{Arc, Mutex};
+
+    // answers every request with an empty body and remembers what it was asked
+    struct Recorder(Arc<Mutex<Vec<HttpRequest>>>);
+
+    impl Transport for Recorder {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+            self.0.lock().unwrap().push(request.clone());
+            Ok(HttpResponse {
+                status: 204,
+                headers: HashMap::new(),
+                body: Vec::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_injected_transport() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let client = Client::new("token")
+            .with_base_url("https://example.com")
+            .with_partner_attribution_id("BN_CODE")
+            .with_transport(Recorder(requests.clone()));
+        crate::web_profile::delete(&client, "XP-1234").unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(1, requests.len());
+        assert_eq!(Method::DELETE, requests[0].method);
+        assert_eq!(
+            "https://example.com/v1/payment-experience/web-profiles/XP-1234",
+            requests[0].url
+        );
+        assert_eq!("Bearer token", requests[0].headers["Authorization"]);
+        assert_eq!(
+            "BN_CODE",
+            requests[0].headers["PayPal-Partner-Attribution-Id"]
+        );
+    }
+
+    // accepts a single connection, and either holds it without answering or, when `trickle`,
+    // answers with a body that never ends, a byte every 50ms, until the test is done
+    fn slow_server(trickle: bool) -> (String, impl FnOnce()) {
+        use std::io::Write;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (done, wait) = std::sync::mpsc::channel::<()>();
+        let server = std::thread::spawn(move || {
+            let (mut held, _) = listener.accept().unwrap();
+            if trickle {
+                // the whole request first, as hyper gives up on answers arriving before it is sent
+                let mut request = Vec::new();
+                let mut byte = [0];
+                while !request.ends_with(b"\r\n\r\n") {
+                    std::io::Read::read_exact(&mut held, &mut byte).unwrap();
+                    request.push(byte[0]);
+                }
+                let head = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\r\n[";
+                held.write_all(head.as_bytes()).unwrap();
+                while let Err(std::sync::mpsc::RecvTimeoutError::Timeout) =
+                    wait.recv_timeout(Duration::from_millis(50))
+                {
+                    if held.write_all(b" ").is_err() {
+                        return;
+                    }
+                }
+            } else {
+                let _ = wait.recv();
+            }
+        });
+        (url, move || {
+            drop(done);
+            server.join().unwrap();
+        })
+    }
+
+    fn assert_times_out(transport: ReqwestTransport, trickle: bool, per_call: Option<Duration>) {
+        let (url, stop) = slow_server(trickle);
+        let mut client = Client::new("token")
+            .with_base_url(&url)
+            .with_transport(transport);
+        if let Some(timeout) = per_call {
+            client = client.with_timeout(timeout);
+        }
+        let started = Instant::now();
+        let err = crate::payment::list(&client).unwrap_err();
+        assert!(err.is_timeout(), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        stop();
+    }
+
+    #[test]
+    fn test_per_call_timeout() {
+        let transport = || {
+            ReqwestTransport::builder()
+                .read_timeout(Duration::from_secs(60))
+                .timeout(Duration::from_secs(60))
+                .build()
+                .unwrap()
+        };
+        let per_call = Some(Duration::from_millis(200));
+        assert_times_out(transport(), false, per_call);
+        assert_times_out(transport(), true, per_call);
+
+        // an injected client cannot be rebuilt, but the deadline still holds as data arrives
+        let injected = ReqwestTransport::new(reqwest::Client::new());
+        assert_times_out(injected, true, per_call);
+    }
+
+    #[test]
+    fn test_total_timeout() {
+        let transport = ReqwestTransport::builder()
+            .read_timeout(Duration::from_secs(60))
+            .timeout(Duration::from_millis(300))
+            .build()
+            .unwrap();
+        assert_times_out(transport.clone(), true, None);
+        assert_times_out(transport.clone(), false, None);
+
+        // however many deadlines are asked for, only a few clients are built
+        for millis in 1..200 {
+            transport
+                .client_for(Some(Duration::from_millis(millis * 7)))
+                .unwrap();
+        }
+        assert!(transport.shorter.lock().unwrap().len() <= READ_TIMEOUT_STEPS.len());
+    }
+}
@@ -1,23 +1,274 @@
+use crate::credentials::AccessToken;
+use crate::error::Error;
+use crate::redact;
+use crate::request::Resp;
 use chrono as chr;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
 
-// paypals reply when requesting a new jwt
+/// PayPal's reply when requesting an access token.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ResponseAccessToken {
-    scope: String,
+    /// What the token grants access to.
+    pub scope: Scopes,
+    /// A value PayPal uses to tell tokens apart.
     pub nonce: String,
-    pub access_token: String,
+    /// The token itself, to be passed to `Client::new`.
+    pub access_token: AccessToken,
+    /// The kind of token, always `Bearer`.
     pub token_type: String,
+    /// The ID of the REST app the token was issued to.
     pub app_id: String,
+    /// How many seconds after it was issued the token expires.
     pub expires_in: i64,
+    /// When the token was requested, set by the functions of this crate that request one. PayPal
+    /// does not send it, so it is `None` for a token parsed from PayPal's reply by other means.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub received_at: Option<chr::DateTime<chr::Utc>>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
+impl ResponseAccessToken {
+    /// When the token expires, counted from `received_at`, if that is known.
+    pub fn expires_at(&self) -> Option<chr::DateTime<chr::Utc>> {
+        Some(self.received_at? + chr::Duration::seconds(self.expires_in))
+    }
+}
+
+string_enum! {
+    /// An OAuth scope, granting access to part of PayPal's API.
+    pub enum Scope {
+        /// A scope this version of the crate does not know about.
+        Unknown(String),
+        /// Logging payers in with PayPal.
+        OpenId = "openid",
+        /// The v1 payments API: payments, sales, authorizations, captures and refunds.
+        Payments = "https://api.paypal.com/v1/payments/.*",
+        /// Creating payments.
+        Payment = "https://uri.paypal.com/services/payments/payment",
+        /// Refunding payments.
+        Refund = "https://uri.paypal.com/services/payments/refund",
+        /// Authorizing and capturing payments.
+        AuthCapture = "https://uri.paypal.com/services/payments/payment/authcapture",
+        /// Real-time payments.
+        RealtimePayment = "https://uri.paypal.com/services/payments/realtimepayment",
+        /// Payments the payer consented to in advance.
+        FuturePayments = "https://uri.paypal.com/services/payments/futurepayments",
+        /// Sending payouts.
+        Payouts = "https://uri.paypal.com/payments/payouts",
+        /// Storing credit cards in the vault.
+        VaultCreditCard = "https://api.paypal.com/v1/vault/credit-card",
+        /// Using the credit cards stored in the vault.
+        VaultCreditCards = "https://api.paypal.com/v1/vault/credit-card/.*",
+        /// Creating and sending invoices.
+        Invoicing = "https://uri.paypal.com/services/invoicing",
+        /// Reading disputes as a buyer.
+        DisputesReadBuyer = "https://uri.paypal.com/services/disputes/read-buyer",
+        /// Reading disputes as a seller.
+        DisputesReadSeller = "https://uri.paypal.com/services/disputes/read-seller",
+        /// Answering disputes as a seller.
+        DisputesUpdateSeller = "https://uri.paypal.com/services/disputes/update-seller",
+        /// Billing plans and subscriptions.
+        Subscriptions = "https://uri.paypal.com/services/subscriptions",
+        /// Managing webhooks.
+        Webhooks = "https://uri.paypal.com/services/applications/webhooks",
+        /// Onboarding sellers as a partner.
+        PartnerReferrals = "https://uri.paypal.com/services/customer/partner-referrals",
+        /// Checking the integration status of onboarded sellers.
+        MerchantIntegrations =
+            "https://uri.paypal.com/services/customer/merchant-integrations/read",
+        /// Adding and updating shipment tracking information.
+        Trackers = "https://uri.paypal.com/services/shipping/trackers/readwrite",
+        /// Searching transactions.
+        TransactionSearch = "https://uri.paypal.com/services/reporting/search/read",
+    }
+}
+
+impl Scope {
+    /// The scopes any one of which lets a call to `path`, such as `/v1/payments/payment/{id}`,
+    /// through, with the one to ask for first. Empty when not known.
+    pub fn accepted_for(path: &str) -> &'static [Scope] {
+        // the first template `path` starts with wins, so longer ones come first
+        static SCOPES: &[(&str, &[Scope])] = &[
+            (
+                "/v1/payments/sale/{id}/refund",
+                &[Scope::Payments, Scope::Refund],
+            ),
+            (
+                "/v1/payments/capture/{id}/refund",
+                &[Scope::Payments, Scope::Refund],
+            ),
+            ("/v1/payments/refund", &[Scope::Payments, Scope::Refund]),
+            (
+                "/v1/payments/sale",
+                &[Scope::Payments, Scope::Payment, Scope::Refund],
+            ),
+            (
+                "/v1/payments/authorization",
+                &[Scope::Payments, Scope::AuthCapture],
+            ),
+            (
+                "/v1/payments/capture",
+                &[Scope::Payments, Scope::AuthCapture],
+            ),
+            (
+                "/v1/payments/payment",
+                &[Scope::Payments, Scope::Payment, Scope::FuturePayments],
+            ),
+            ("/v1/payments/payouts", &[Scope::Payouts, Scope::Payments]),
+            ("/v1/payments/", &[Scope::Payments]),
+            (
+                "/v2/checkout/orders",
+                &[Scope::Payment, Scope::AuthCapture, Scope::Payments],
+            ),
+            (
+                "/v2/payments/captures/{id}/refund",
+                &[Scope::Refund, Scope::Payments],
+            ),
+            ("/v2/payments/refunds", &[Scope::Refund, Scope::Payments]),
+            ("/v2/payments/", &[Scope::AuthCapture, Scope::Payments]),
+            ("/v2/customer/partner-referrals", &[Scope::PartnerReferrals]),
+            ("/v1/customer/partners/", &[Scope::MerchantIntegrations]),
+            ("/v1/shipping/trackers", &[Scope::Trackers]),
+            (
+                "/v1/vault/credit-card",
+                &[Scope::VaultCreditCard, Scope::VaultCreditCards],
+            ),
+            ("/v1/reporting/transactions", &[Scope::TransactionSearch]),
+            ("/v1/notifications/", &[Scope::Webhooks]),
+            ("/v1/billing/", &[Scope::Subscriptions]),
+            ("/v2/invoicing/", &[Scope::Invoicing]),
+            (
+                "/v1/customer/disputes",
+                &[
+                    Scope::DisputesReadSeller,
+                    Scope::DisputesUpdateSeller,
+                    Scope::DisputesReadBuyer,
+                ],
+            ),
+        ];
+        SCOPES
+            .iter()
+            .find(|(template, _)| path.starts_with(template))
+            .map_or(&[], |(_, scopes)| scopes)
+    }
+}
+
+/// The set of scopes an access token grants, sent by PayPal as a space-separated list.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Scopes(Vec<Scope>);
+
+impl Scopes {
+    /// Whether `scope` is among the scopes.
+    pub fn contains(&self, scope: &Scope) -> bool {
+        self.0.contains(scope)
+    }
+
+    /// The scopes, in the order PayPal listed them.
+    pub fn iter(&self) -> std::slice::Iter<'_, Scope> {
+        self.0.iter()
+    }
+
+    /// Whether there are no scopes at all.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl std::iter::FromIterator<Scope> for Scopes {
+    fn from_iter<I: IntoIterator<Item = Scope>>(scopes: I) -> Self {
+        let mut set = Vec::new();
+        for scope in scopes {
+            if !set.contains(&scope) {
+                set.push(scope);
+            }
+        }
+        Scopes(set)
+    }
+}
+
+impl<'a> IntoIterator for &'a Scopes {
+    type Item = &'a Scope;
+    type IntoIter = std::slice::Iter<'a, Scope>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl fmt::Display for Scopes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scopes: Vec<&str> = self.iter().map(Scope::as_str).collect();
+        f.write_str(&scopes.join(" "))
+    }
+}
+
+impl Serialize for Scopes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Scopes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let scopes = String::deserialize(deserializer)?;
+        Ok(scopes.split_whitespace().map(Scope::from).collect())
+    }
+}
+
+/// The body of a request creating a payment. See `payment::create`.
 #[derive(Serialize, Debug)]
 pub struct RequestNewPayment {
+    /// The payment intent.
     pub intent: PaymentIntent,
+    /// The source of the funds for this payment.
     pub payer: Payer,
+    /// What the payment is for and who fulfills it.
     pub transactions: Vec<Transaction>,
+    /// Where PayPal sends the payer after they approve or cancel the payment.
     pub redirect_urls: RedirectUrls,
+    /// The ID of the web experience profile to show the payer, as returned by
+    /// `web_profile::create`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experience_profile_id: Option<String>,
+}
+
+impl RequestNewPayment {
+    /// Builds the body of a payment paid with `method`, redirecting the payer to `return_url` once
+    /// they approve it or to `cancel_url` if they cancel.
+    pub fn new(
+        return_url: &str,
+        cancel_url: &str,
+        method: PaymentMethod,
+        intent: PaymentIntent,
+        transactions: Vec<Transaction>,
+    ) -> RequestNewPayment {
+        RequestNewPayment {
+            redirect_urls: RedirectUrls {
+                return_url: return_url.into(),
+                cancel_url: cancel_url.into(),
+                extra: Default::default(),
+            },
+            payer: Payer {
+                payment_method: method,
+                funding_instruments: None,
+                status: None,
+                extra: Default::default(),
+            },
+            intent,
+            transactions,
+            experience_profile_id: None,
+        }
+    }
+
+    /// Shows the payer the checkout pages of the web experience profile with the given ID.
+    pub fn with_experience_profile(mut self, profile_id: &str) -> RequestNewPayment {
+        self.experience_profile_id = Some(profile_id.to_string());
+        self
+    }
 }
 
 /// A single payment in PayPal's system, either completed or not.
@@ -31,29 +282,58 @@ pub struct Payment {
     /// direct debit.
     pub payer: Payer,
     /// Use the application context resource to customize payment flow experience for your buyers.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub application_context: Option<ApplicationContext>,
     /// An array of payment-related transactions. A transaction defines what the payment is for and
     /// who fulfills the payment. For update and execute payment calls, the transactions object
     /// accepts the amount object only.
     pub transactions: Vec<Transaction>,
     /// The state of the payment.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub state: Option<State>,
     /// The PayPal-generated ID for the merchant's payment experience profile. For information, see
     /// [create web experience profile](https://developer.paypal.com/docs/api/payment-experience/#web-profiles_create).
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub experience_profile_id: Option<String>,
     /// A free-form field that clients can use to send a note to the payer.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub note_to_payer: Option<String>,
     /// A set of redirect URLs that you provide for PayPal-based payments.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub redirect_urls: Option<RedirectUrls>,
     /// The reason code for a payment failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub failure_reason: Option<FailureReason>,
     /// The date and time when the payment was created.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub create_time: Option<chr::DateTime<chr::Utc>>,
     /// The date and time when the payment was updated.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub update_time: Option<chr::DateTime<chr::Utc>>,
     /// An array of request-related
     /// [HATEOAS links](https://developer.paypal.com/docs/api/reference/api-responses/#hateoas-links).
     pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Payment {
+    /// Finds the link with the given relation type.
+    pub fn link(&self, rel: &LinkRel) -> Option<&LinkDescription> {
+        find_link(&self.links, rel)
+    }
+
+    /// The URL to redirect the payer to, so that they approve the payment.
+    pub fn approval_url(&self) -> Option<&str> {
+        self.link(&LinkRel::ApprovalUrl)
+            .map(|link| link.href.as_str())
+    }
+
+    /// The link that executes the payment once the payer approved it.
+    pub fn execute_link(&self) -> Option<&LinkDescription> {
+        self.link(&LinkRel::Execute)
+    }
 }
 
 /// Used to customize the payment flow page.
@@ -61,6 +341,7 @@ pub struct Payment {
 pub struct ApplicationContext {
     /// A label that overrides the business name in the merchant's PayPal account on the PayPal
     /// checkout pages.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub brand_name: Option<String>,
     /// The locale of pages that the PayPal payment experience displays. A valid value is AU, AT,
     /// BE, BR, CA, CH, CN, DE, ES, GB, FR, IT, NL, PL, PT, RU, or US. A five-character code is
@@ -75,24 +356,29 @@ pub struct ApplicationContext {
     pub shipping_preference: ShippingPreference,
     /// The user action.
     pub user_action: String,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-/// The shipping preference.
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum ShippingPreference {
-    /// Redacts the shipping address from the PayPal pages. Recommended for digital goods.
-    NoShipping,
-    /// Uses the customer-selected shipping address on PayPal pages.
-    GetFromFile,
-    /// If available, uses the merchant-provided shipping address, which the customer cannot change
-    /// on the PayPal pages. If the merchant does not provide an address, the customer can enter
-    /// the address on PayPal pages.
-    SetProvidedAddress,
+string_enum! {
+    /// The shipping preference.
+    pub enum ShippingPreference {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// Redacts the shipping address from the PayPal pages. Recommended for digital goods.
+        NoShipping = "NO_SHIPPING",
+        /// Uses the customer-selected shipping address on PayPal pages.
+        GetFromFile = "GET_FROM_FILE",
+        /// If available, uses the merchant-provided shipping address, which the customer cannot
+        /// change on the PayPal pages. If the merchant does not provide an address, the customer
+        /// can enter the address on PayPal pages.
+        SetProvidedAddress = "SET_PROVIDED_ADDRESS",
+    }
 }
 
 /// A struct containing a url and some metadata.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct LinkDescription {
     /// The complete target URL. To make the related call, combine the method with this URI
     /// Template-formatted link. For pre-processing, include the `$`, `(`, and `)` characters. The
@@ -100,48 +386,129 @@ pub struct LinkDescription {
     pub href: String,
     /// The link relation type, which serves as an ID for a link that unambiguously describes the
     /// semantics of the link.
-    pub rel: String,
+    pub rel: LinkRel,
     /// The HTTP method required to make the related call.
     /// Possible values: `GET`, `POST`, `PUT`, `DELETE`, `HEAD`, `CONNECT`, `OPTIONS`, `PATCH`.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub method: Option<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+string_enum! {
+    /// The relation type of a HATEOAS link, describing what following it does.
+    pub enum LinkRel {
+        /// Any other relation type.
+        Unknown(String),
+        /// The resource itself.
+        SelfLink = "self",
+        /// Where to redirect the payer to approve a v1 payment.
+        ApprovalUrl = "approval_url",
+        /// Where to redirect the payer to approve a v2 order.
+        Approve = "approve",
+        /// Where to redirect the payer to complete an action, such as 3D Secure authentication.
+        PayerAction = "payer-action",
+        /// Executes an approved v1 payment.
+        Execute = "execute",
+        /// Updates the resource.
+        Update = "update",
+        /// Replaces the resource.
+        Replace = "replace",
+        /// Deletes the resource.
+        Delete = "delete",
+        /// Captures an authorized payment or an approved order.
+        Capture = "capture",
+        /// Authorizes an approved order.
+        Authorize = "authorize",
+        /// Reauthorizes an authorization.
+        Reauthorize = "reauthorize",
+        /// Voids an authorization or order.
+        Void = "void",
+        /// Refunds a sale or capture.
+        Refund = "refund",
+        /// The sale a resource belongs to.
+        Sale = "sale",
+        /// The authorization a resource belongs to.
+        Authorization = "authorization",
+        /// The payment a resource belongs to.
+        ParentPayment = "parent_payment",
+        /// The resource one level up, for example the capture a refund belongs to.
+        Up = "up",
+        /// Where to send a seller to complete a partner referral.
+        ActionUrl = "action_url",
+        /// The next page of a list.
+        Next = "next",
+        /// The previous page of a list.
+        Prev = "prev",
+        /// The first page of a list.
+        First = "first",
+        /// The last page of a list.
+        Last = "last",
+    }
+}
+
+// The first link with the given relation type
+fn find_link<'a>(links: &'a [LinkDescription], rel: &LinkRel) -> Option<&'a LinkDescription> {
+    links.iter().find(|link| &link.rel == rel)
 }
 
 /// Returned when listing all payments in the system.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct ListPaymentResponse {
     /// A vector of the payments
     pub payments: Vec<Payment>,
     /// The number of payments, should be equal to `response.payments.len()`.
     pub count: usize,
     /// The ID of the element to use to get the next range of results.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub next_id: Option<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-/// Represents the state of a payment.
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "snake_case")]
-pub enum State {
-    /// The transaction was successfully created.
-    Created,
-    /// The customer approved the transaction. The state changes from created to approved on
-    /// generation of the sale_id for sale transactions, authorization_id for authorization
-    /// transactions, or order_id for order transactions.
-    Approved,
-    /// The transaction request failed.
-    Failed,
-}
-
-/// The reason code for a payment failure.
-#[allow(missing_docs)] // not documented by PayPal, but seems trivial
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum FailureReason {
-    UnableToCompleteTransaction,
-    InvalidPaymentMethod,
-    PayerCannotPay,
-    CannotPayThisPayee,
-    RedirectRequired,
-    PayeeFilterRestrictions,
+string_enum! {
+    /// Represents the state of a payment.
+    pub enum State {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// The transaction was successfully created.
+        Created = "created",
+        /// The customer approved the transaction. The state changes from created to approved on
+        /// generation of the sale_id for sale transactions, authorization_id for authorization
+        /// transactions, or order_id for order transactions.
+        Approved = "approved",
+        /// The transaction request failed.
+        Failed = "failed",
+        /// The payment was canceled by the payer or the merchant.
+        Canceled = "canceled",
+        /// The payment was not approved or executed in time and can no longer be completed.
+        Expired = "expired",
+        /// The payment is pending, for example while PayPal reviews it.
+        Pending = "pending",
+        /// The payment was completed and the funds were transferred.
+        Completed = "completed",
+        /// Part of the payment was refunded to the payer.
+        PartiallyRefunded = "partially_refunded",
+        /// The whole payment was refunded to the payer.
+        Refunded = "refunded",
+    }
+}
+
+string_enum! {
+    /// The reason code for a payment failure.
+    #[allow(missing_docs)] // not documented by PayPal, but seems trivial
+    pub enum FailureReason {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        UnableToCompleteTransaction = "UNABLE_TO_COMPLETE_TRANSACTION",
+        InvalidPaymentMethod = "INVALID_PAYMENT_METHOD",
+        PayerCannotPay = "PAYER_CANNOT_PAY",
+        CannotPayThisPayee = "CANNOT_PAY_THIS_PAYEE",
+        RedirectRequired = "REDIRECT_REQUIRED",
+        PayeeFilterRestrictions = "PAYEE_FILTER_RESTRICTIONS",
+    }
 }
 
 /// Struct containing urls where the users is redirected after visiting the paypal site.
@@ -153,18 +520,23 @@ pub struct RedirectUrls {
     /// The URL where the payer is redirected after he or she cancels the payment.
     /// *Required for PayPal account payments.*
     pub cancel_url: String,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-/// The type of payment that is created
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "snake_case")]
-pub enum PaymentIntent {
-    /// Makes an immediate payment.
-    Sale,
-    /// Authorizes a payment for capture later.
-    Authorize,
-    /// Creates an order.
-    Order,
+string_enum! {
+    /// The type of payment that is created
+    pub enum PaymentIntent {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// Makes an immediate payment.
+        Sale = "sale",
+        /// Authorizes a payment for capture later.
+        Authorize = "authorize",
+        /// Creates an order.
+        Order = "order",
+    }
 }
 
 /// A paypal account that can be charged.
@@ -173,91 +545,221 @@ pub struct Payer {
     /// The payment method.
     pub payment_method: PaymentMethod,
     /// The status of payer's PayPal account
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<PayerStatus>,
     /// An array of a single funding instrument for the current payment. Valid only and required
     /// for the credit card payment method. The array must include either a credit_card or
     /// credit_card_token object. If the array contains more than one instrument, the payment is
     /// declined.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub funding_instruments: Option<Vec<FundingInstrument>>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-/// Represents one of the ways paypal is able to process payments.
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "snake_case")]
-pub enum PaymentMethod {
-    /// Credit card.
-    CreditCard,
-    /// A PayPal Wallet payment.
-    Paypal,
-    /// Pay upon invoice.
-    PayUponInvoice,
-    /// Carrier.
-    Carrier,
-    /// Alternate payment.
-    AlternatePayment,
-    /// Bank.
-    Bank,
-}
-
-/// The status of a Payer
-#[allow(missing_docs)] // undocumented by PayPal
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum PayerStatus {
-    Verified,
-    Unverified,
+string_enum! {
+    /// Represents one of the ways paypal is able to process payments.
+    pub enum PaymentMethod {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// Credit card.
+        CreditCard = "credit_card",
+        /// A PayPal Wallet payment.
+        Paypal = "paypal",
+        /// Pay upon invoice.
+        PayUponInvoice = "pay_upon_invoice",
+        /// Carrier.
+        Carrier = "carrier",
+        /// Alternate payment.
+        AlternatePayment = "alternate_payment",
+        /// Bank.
+        Bank = "bank",
+    }
+}
+
+string_enum! {
+    /// The status of a Payer
+    #[allow(missing_docs)] // undocumented by PayPal
+    pub enum PayerStatus {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        Verified = "VERIFIED",
+        Unverified = "UNVERIFIED",
+    }
 }
 
 /// A single transaction in paypals system. A payment consists of zero or more transactions
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Transaction {
     /// The amount that is charged when this transaction is completed
     pub amount: TransactionAmount,
+    /// The merchant who receives the funds, when a partner creates the payment on behalf of a
+    /// connected merchant. Defaults to the owner of the bearer token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payee: Option<Payee>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-/// A currency-aware representation of an amount of money
+/// The merchant who receives the funds of a transaction.
 #[derive(Serialize, Deserialize, Debug)]
+pub struct Payee {
+    /// The email address associated with the payee's PayPal account.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// The encrypted PayPal account ID of the payee.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merchant_id: Option<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A completed v1 payment with the `sale` intent, which can be refunded.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Sale {
+    /// The ID of the sale.
+    pub id: String,
+    /// The state of the sale.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<State>,
+    /// The amount of the sale.
+    pub amount: TransactionAmount,
+    /// The ID of the payment the sale belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_payment: Option<String>,
+    /// The date and time when the sale was made.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<chr::DateTime<chr::Utc>>,
+    /// An array of request-related HATEOAS links.
+    #[serde(default)]
+    pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A refund of a v1 sale.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Refund {
+    /// The ID of the refund.
+    pub id: String,
+    /// The state of the refund.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<State>,
+    /// The amount refunded to the payer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<TransactionAmount>,
+    /// The ID of the refunded sale.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sale_id: Option<String>,
+    /// The ID of the payment the refunded sale belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_payment: Option<String>,
+    /// The date and time when the refund was made.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<chr::DateTime<chr::Utc>>,
+    /// An array of request-related HATEOAS links.
+    #[serde(default)]
+    pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A currency-aware representation of an amount of money
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct TransactionAmount {
     /// The ISO 4217 currency code, for example "USD" or "EUR"
     pub currency: String,
     /// The amount of currency that is to be charge, for example "10" or "12.34"
     pub total: String,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Data about a credit card.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FundingInstrument {
     /// Full representation of a credit card
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub credit_card: Option<CreditCard>,
     /// Token representation of a credit card
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub credit_card_token: Option<CreditCardToken>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-/// A credit card
-#[derive(Serialize, Deserialize, Debug)]
+/// A credit card. Its `Debug` output only shows the last four digits of the number, and never the
+/// CVV.
+#[derive(Serialize, Deserialize)]
 pub struct CreditCard {
     /// The credit card number. Value is numeric characters only with no spaces or punctuation.
     /// Must conform to the modulo and length required by each credit card type. Redacted in
     /// responses.
     pub number: String,
-    /// The credit card type. Value is visa, mastercard, discover, or amex. Do not use these
-    /// lowercase values for display.
+    /// The credit card brand.
     #[serde(rename = "type")]
-    pub _type: String,
+    pub _type: CardBrand,
     /// The expiration month with no leading zero. Value is from 1 to 12.
     pub expire_month: i32,
     /// The four-digit expiration year.
     pub expire_year: i32,
     /// The three- to four-digit card validation code.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub cvv2: Option<String>,
     /// The card holder's first name.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub first_name: Option<String>,
     /// The card holder's last name.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub last_name: Option<String>,
     /// The billing address for this card.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub billing_address: Option<Address>,
     /// An array of request-related HATEOAS links.
     pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl fmt::Debug for CreditCard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CreditCard")
+            .field("number", &redact::card_number(&self.number))
+            .field("_type", &self._type)
+            .field("expire_month", &self.expire_month)
+            .field("expire_year", &self.expire_year)
+            .field("cvv2", &self.cvv2.as_ref().map(|_| redact::REDACTED))
+            .field("first_name", &self.first_name)
+            .field("last_name", &self.last_name)
+            .field("billing_address", &self.billing_address)
+            .field("links", &self.links)
+            .field("extra", &self.extra)
+            .finish()
+    }
+}
+
+string_enum! {
+    /// The brand of a credit card. PayPal's lowercase values are not meant for display.
+    pub enum CardBrand {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// Visa.
+        Visa = "visa",
+        /// Mastercard.
+        Mastercard = "mastercard",
+        /// Discover.
+        Discover = "discover",
+        /// American Express.
+        Amex = "amex",
+    }
 }
 
 /// Represents an address.
@@ -266,39 +768,559 @@ pub struct Address {
     /// The first line of the address. For example, number, street, and so on.
     pub line1: String,
     /// The second line of the address. For example, suite or apartment number.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub line2: Option<String>,
     /// The city name.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub city: Option<String>,
-    /// The two-character ISO 3166-1 code that identifies the country or region.
-    pub country_code: String,
+    /// The country or region.
+    pub country_code: CountryCode,
     /// The postal code, which is the zip code or equivalent. Typically required for countries with
     /// a postal code or an equivalent.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub postal_code: Option<String>,
     /// The code for a US state or the equivalent for other countries. Required for transactions if
     /// the address is in one of these countries: Argentina, Brazil, Canada, China, India, Italy,
     /// Japan, Mexico, Thailand, or United States. Maximum length is 40 single-byte characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub state: Option<String>,
     /// The phone number, in E.123 format. Maximum length is 50 characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub phone: Option<String>,
     /// The address normalization status.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub normalization_status: Option<NormalizationStatus>,
     /// The type of address. For example, HOME_OR_WORK, GIFT, and so on.
-    #[serde(rename = "type")]
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub _type: Option<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-/// The address normalization status. Returned only for payers from Brazil.
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum NormalizationStatus {
-    /// Unknown
-    Unknown,
-    /// Unnormalized user preferred.
-    UnnormalizedUserPreferred,
-    /// Normalized.
-    Normalized,
-    /// Unnormalized.
-    Unnormalized,
+string_enum! {
+    /// A two-character ISO 3166-1 country code, plus PayPal's `C2` for cross-border transactions
+    /// with China.
+    pub enum CountryCode {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// Andorra.
+        Andorra = "AD",
+        /// United Arab Emirates.
+        UnitedArabEmirates = "AE",
+        /// Afghanistan.
+        Afghanistan = "AF",
+        /// Antigua and Barbuda.
+        AntiguaAndBarbuda = "AG",
+        /// Anguilla.
+        Anguilla = "AI",
+        /// Albania.
+        Albania = "AL",
+        /// Armenia.
+        Armenia = "AM",
+        /// Angola.
+        Angola = "AO",
+        /// Antarctica.
+        Antarctica = "AQ",
+        /// Argentina.
+        Argentina = "AR",
+        /// American Samoa.
+        AmericanSamoa = "AS",
+        /// Austria.
+        Austria = "AT",
+        /// Australia.
+        Australia = "AU",
+        /// Aruba.
+        Aruba = "AW",
+        /// Åland Islands.
+        AlandIslands = "AX",
+        /// Azerbaijan.
+        Azerbaijan = "AZ",
+        /// Bosnia and Herzegovina.
+        BosniaAndHerzegovina = "BA",
+        /// Barbados.
+        Barbados = "BB",
+        /// Bangladesh.
+        Bangladesh = "BD",
+        /// Belgium.
+        Belgium = "BE",
+        /// Burkina Faso.
+        BurkinaFaso = "BF",
+        /// Bulgaria.
+        Bulgaria = "BG",
+        /// Bahrain.
+        Bahrain = "BH",
+        /// Burundi.
+        Burundi = "BI",
+        /// Benin.
+        Benin = "BJ",
+        /// Saint Barthélemy.
+        SaintBarthelemy = "BL",
+        /// Bermuda.
+        Bermuda = "BM",
+        /// Brunei.
+        Brunei = "BN",
+        /// Bolivia.
+        Bolivia = "BO",
+        /// Caribbean Netherlands.
+        CaribbeanNetherlands = "BQ",
+        /// Brazil.
+        Brazil = "BR",
+        /// Bahamas.
+        Bahamas = "BS",
+        /// Bhutan.
+        Bhutan = "BT",
+        /// Bouvet Island.
+        BouvetIsland = "BV",
+        /// Botswana.
+        Botswana = "BW",
+        /// Belarus.
+        Belarus = "BY",
+        /// Belize.
+        Belize = "BZ",
+        /// Canada.
+        Canada = "CA",
+        /// Cocos Islands.
+        CocosIslands = "CC",
+        /// Congo (Kinshasa).
+        CongoKinshasa = "CD",
+        /// Central African Republic.
+        CentralAfricanRepublic = "CF",
+        /// Congo (Brazzaville).
+        CongoBrazzaville = "CG",
+        /// Switzerland.
+        Switzerland = "CH",
+        /// Côte d'Ivoire.
+        CoteDIvoire = "CI",
+        /// Cook Islands.
+        CookIslands = "CK",
+        /// Chile.
+        Chile = "CL",
+        /// Cameroon.
+        Cameroon = "CM",
+        /// China.
+        China = "CN",
+        /// China, as PayPal identifies it for cross-border transactions.
+        ChinaWorldwide = "C2",
+        /// Colombia.
+        Colombia = "CO",
+        /// Costa Rica.
+        CostaRica = "CR",
+        /// Cuba.
+        Cuba = "CU",
+        /// Cape Verde.
+        CapeVerde = "CV",
+        /// Curaçao.
+        Curacao = "CW",
+        /// Christmas Island.
+        ChristmasIsland = "CX",
+        /// Cyprus.
+        Cyprus = "CY",
+        /// Czechia.
+        Czechia = "CZ",
+        /// Germany.
+        Germany = "DE",
+        /// Djibouti.
+        Djibouti = "DJ",
+        /// Denmark.
+        Denmark = "DK",
+        /// Dominica.
+        Dominica = "DM",
+        /// Dominican Republic.
+        DominicanRepublic = "DO",
+        /// Algeria.
+        Algeria = "DZ",
+        /// Ecuador.
+        Ecuador = "EC",
+        /// Estonia.
+        Estonia = "EE",
+        /// Egypt.
+        Egypt = "EG",
+        /// Western Sahara.
+        WesternSahara = "EH",
+        /// Eritrea.
+        Eritrea = "ER",
+        /// Spain.
+        Spain = "ES",
+        /// Ethiopia.
+        Ethiopia = "ET",
+        /// Finland.
+        Finland = "FI",
+        /// Fiji.
+        Fiji = "FJ",
+        /// Falkland Islands.
+        FalklandIslands = "FK",
+        /// Micronesia.
+        Micronesia = "FM",
+        /// Faroe Islands.
+        FaroeIslands = "FO",
+        /// France.
+        France = "FR",
+        /// Gabon.
+        Gabon = "GA",
+        /// United Kingdom.
+        UnitedKingdom = "GB",
+        /// Grenada.
+        Grenada = "GD",
+        /// Georgia.
+        Georgia = "GE",
+        /// French Guiana.
+        FrenchGuiana = "GF",
+        /// Guernsey.
+        Guernsey = "GG",
+        /// Ghana.
+        Ghana = "GH",
+        /// Gibraltar.
+        Gibraltar = "GI",
+        /// Greenland.
+        Greenland = "GL",
+        /// Gambia.
+        Gambia = "GM",
+        /// Guinea.
+        Guinea = "GN",
+        /// Guadeloupe.
+        Guadeloupe = "GP",
+        /// Equatorial Guinea.
+        EquatorialGuinea = "GQ",
+        /// Greece.
+        Greece = "GR",
+        /// South Georgia and South Sandwich Islands.
+        SouthGeorgiaAndSouthSandwichIslands = "GS",
+        /// Guatemala.
+        Guatemala = "GT",
+        /// Guam.
+        Guam = "GU",
+        /// Guinea-Bissau.
+        GuineaBissau = "GW",
+        /// Guyana.
+        Guyana = "GY",
+        /// Hong Kong.
+        HongKong = "HK",
+        /// Heard Island and McDonald Islands.
+        HeardIslandAndMcDonaldIslands = "HM",
+        /// Honduras.
+        Honduras = "HN",
+        /// Croatia.
+        Croatia = "HR",
+        /// Haiti.
+        Haiti = "HT",
+        /// Hungary.
+        Hungary = "HU",
+        /// Indonesia.
+        Indonesia = "ID",
+        /// Ireland.
+        Ireland = "IE",
+        /// Israel.
+        Israel = "IL",
+        /// Isle of Man.
+        IsleOfMan = "IM",
+        /// India.
+        India = "IN",
+        /// British Indian Ocean Territory.
+        BritishIndianOceanTerritory = "IO",
+        /// Iraq.
+        Iraq = "IQ",
+        /// Iran.
+        Iran = "IR",
+        /// Iceland.
+        Iceland = "IS",
+        /// Italy.
+        Italy = "IT",
+        /// Jersey.
+        Jersey = "JE",
+        /// Jamaica.
+        Jamaica = "JM",
+        /// Jordan.
+        Jordan = "JO",
+        /// Japan.
+        Japan = "JP",
+        /// Kenya.
+        Kenya = "KE",
+        /// Kyrgyzstan.
+        Kyrgyzstan = "KG",
+        /// Cambodia.
+        Cambodia = "KH",
+        /// Kiribati.
+        Kiribati = "KI",
+        /// Comoros.
+        Comoros = "KM",
+        /// Saint Kitts and Nevis.
+        SaintKittsAndNevis = "KN",
+        /// North Korea.
+        NorthKorea = "KP",
+        /// South Korea.
+        SouthKorea = "KR",
+        /// Kuwait.
+        Kuwait = "KW",
+        /// Cayman Islands.
+        CaymanIslands = "KY",
+        /// Kazakhstan.
+        Kazakhstan = "KZ",
+        /// Laos.
+        Laos = "LA",
+        /// Lebanon.
+        Lebanon = "LB",
+        /// Saint Lucia.
+        SaintLucia = "LC",
+        /// Liechtenstein.
+        Liechtenstein = "LI",
+        /// Sri Lanka.
+        SriLanka = "LK",
+        /// Liberia.
+        Liberia = "LR",
+        /// Lesotho.
+        Lesotho = "LS",
+        /// Lithuania.
+        Lithuania = "LT",
+        /// Luxembourg.
+        Luxembourg = "LU",
+        /// Latvia.
+        Latvia = "LV",
+        /// Libya.
+        Libya = "LY",
+        /// Morocco.
+        Morocco = "MA",
+        /// Monaco.
+        Monaco = "MC",
+        /// Moldova.
+        Moldova = "MD",
+        /// Montenegro.
+        Montenegro = "ME",
+        /// Saint Martin.
+        SaintMartin = "MF",
+        /// Madagascar.
+        Madagascar = "MG",
+        /// Marshall Islands.
+        MarshallIslands = "MH",
+        /// North Macedonia.
+        NorthMacedonia = "MK",
+        /// Mali.
+        Mali = "ML",
+        /// Myanmar.
+        Myanmar = "MM",
+        /// Mongolia.
+        Mongolia = "MN",
+        /// Macao.
+        Macao = "MO",
+        /// Northern Mariana Islands.
+        NorthernMarianaIslands = "MP",
+        /// Martinique.
+        Martinique = "MQ",
+        /// Mauritania.
+        Mauritania = "MR",
+        /// Montserrat.
+        Montserrat = "MS",
+        /// Malta.
+        Malta = "MT",
+        /// Mauritius.
+        Mauritius = "MU",
+        /// Maldives.
+        Maldives = "MV",
+        /// Malawi.
+        Malawi = "MW",
+        /// Mexico.
+        Mexico = "MX",
+        /// Malaysia.
+        Malaysia = "MY",
+        /// Mozambique.
+        Mozambique = "MZ",
+        /// Namibia.
+        Namibia = "NA",
+        /// New Caledonia.
+        NewCaledonia = "NC",
+        /// Niger.
+        Niger = "NE",
+        /// Norfolk Island.
+        NorfolkIsland = "NF",
+        /// Nigeria.
+        Nigeria = "NG",
+        /// Nicaragua.
+        Nicaragua = "NI",
+        /// Netherlands.
+        Netherlands = "NL",
+        /// Norway.
+        Norway = "NO",
+        /// Nepal.
+        Nepal = "NP",
+        /// Nauru.
+        Nauru = "NR",
+        /// Niue.
+        Niue = "NU",
+        /// New Zealand.
+        NewZealand = "NZ",
+        /// Oman.
+        Oman = "OM",
+        /// Panama.
+        Panama = "PA",
+        /// Peru.
+        Peru = "PE",
+        /// French Polynesia.
+        FrenchPolynesia = "PF",
+        /// Papua New Guinea.
+        PapuaNewGuinea = "PG",
+        /// Philippines.
+        Philippines = "PH",
+        /// Pakistan.
+        Pakistan = "PK",
+        /// Poland.
+        Poland = "PL",
+        /// Saint Pierre and Miquelon.
+        SaintPierreAndMiquelon = "PM",
+        /// Pitcairn Islands.
+        PitcairnIslands = "PN",
+        /// Puerto Rico.
+        PuertoRico = "PR",
+        /// Palestine.
+        Palestine = "PS",
+        /// Portugal.
+        Portugal = "PT",
+        /// Palau.
+        Palau = "PW",
+        /// Paraguay.
+        Paraguay = "PY",
+        /// Qatar.
+        Qatar = "QA",
+        /// Réunion.
+        Reunion = "RE",
+        /// Romania.
+        Romania = "RO",
+        /// Serbia.
+        Serbia = "RS",
+        /// Russia.
+        Russia = "RU",
+        /// Rwanda.
+        Rwanda = "RW",
+        /// Saudi Arabia.
+        SaudiArabia = "SA",
+        /// Solomon Islands.
+        SolomonIslands = "SB",
+        /// Seychelles.
+        Seychelles = "SC",
+        /// Sudan.
+        Sudan = "SD",
+        /// Sweden.
+        Sweden = "SE",
+        /// Singapore.
+        Singapore = "SG",
+        /// Saint Helena.
+        SaintHelena = "SH",
+        /// Slovenia.
+        Slovenia = "SI",
+        /// Svalbard and Jan Mayen.
+        SvalbardAndJanMayen = "SJ",
+        /// Slovakia.
+        Slovakia = "SK",
+        /// Sierra Leone.
+        SierraLeone = "SL",
+        /// San Marino.
+        SanMarino = "SM",
+        /// Senegal.
+        Senegal = "SN",
+        /// Somalia.
+        Somalia = "SO",
+        /// Suriname.
+        Suriname = "SR",
+        /// South Sudan.
+        SouthSudan = "SS",
+        /// São Tomé and Príncipe.
+        SaoTomeAndPrincipe = "ST",
+        /// El Salvador.
+        ElSalvador = "SV",
+        /// Sint Maarten.
+        SintMaarten = "SX",
+        /// Syria.
+        Syria = "SY",
+        /// Eswatini.
+        Eswatini = "SZ",
+        /// Turks and Caicos Islands.
+        TurksAndCaicosIslands = "TC",
+        /// Chad.
+        Chad = "TD",
+        /// French Southern Territories.
+        FrenchSouthernTerritories = "TF",
+        /// Togo.
+        Togo = "TG",
+        /// Thailand.
+        Thailand = "TH",
+        /// Tajikistan.
+        Tajikistan = "TJ",
+        /// Tokelau.
+        Tokelau = "TK",
+        /// Timor-Leste.
+        TimorLeste = "TL",
+        /// Turkmenistan.
+        Turkmenistan = "TM",
+        /// Tunisia.
+        Tunisia = "TN",
+        /// Tonga.
+        Tonga = "TO",
+        /// Turkey.
+        Turkey = "TR",
+        /// Trinidad and Tobago.
+        TrinidadAndTobago = "TT",
+        /// Tuvalu.
+        Tuvalu = "TV",
+        /// Taiwan.
+        Taiwan = "TW",
+        /// Tanzania.
+        Tanzania = "TZ",
+        /// Ukraine.
+        Ukraine = "UA",
+        /// Uganda.
+        Uganda = "UG",
+        /// United States Minor Outlying Islands.
+        UnitedStatesMinorOutlyingIslands = "UM",
+        /// United States.
+        UnitedStates = "US",
+        /// Uruguay.
+        Uruguay = "UY",
+        /// Uzbekistan.
+        Uzbekistan = "UZ",
+        /// Vatican City.
+        VaticanCity = "VA",
+        /// Saint Vincent and the Grenadines.
+        SaintVincentAndTheGrenadines = "VC",
+        /// Venezuela.
+        Venezuela = "VE",
+        /// British Virgin Islands.
+        BritishVirginIslands = "VG",
+        /// U.S. Virgin Islands.
+        UsVirginIslands = "VI",
+        /// Vietnam.
+        Vietnam = "VN",
+        /// Vanuatu.
+        Vanuatu = "VU",
+        /// Wallis and Futuna.
+        WallisAndFutuna = "WF",
+        /// Samoa.
+        Samoa = "WS",
+        /// Yemen.
+        Yemen = "YE",
+        /// Mayotte.
+        Mayotte = "YT",
+        /// South Africa.
+        SouthAfrica = "ZA",
+        /// Zambia.
+        Zambia = "ZM",
+        /// Zimbabwe.
+        Zimbabwe = "ZW",
+    }
+}
+
+string_enum! {
+    /// The address normalization status. Returned only for payers from Brazil.
+    pub enum NormalizationStatus {
+        /// A value this version of the crate does not know about.
+        Other(String),
+        /// Unknown.
+        Unknown = "UNKNOWN",
+        /// Unnormalized user preferred.
+        UnnormalizedUserPreferred = "UNNORMALIZED_USER_PREFERRED",
+        /// Normalized.
+        Normalized = "NORMALIZED",
+        /// Unnormalized.
+        Unnormalized = "UNNORMALIZED",
+    }
 }
 
 /// A credit card in token representation.
@@ -311,15 +1333,1374 @@ pub struct CreditCardToken {
     /// cards and can be any value, such as a UUID, user name, or email address. *Required* when
     /// you use a vaulted credit card and if a payer_id was originally provided when you vaulted
     /// the credit card.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub payer_id: Option<String>,
     /// The last four digits of the stored credit card number.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub last4: Option<String>,
-    /// The credit card type. Value is visa, mastercard, discover, or amex. Do not use these
-    /// lowercase values for display.
-    #[serde(rename = "type")]
-    pub _type: Option<String>,
+    /// The credit card brand.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub _type: Option<CardBrand>,
     /// The expiration month with no leading zero. Value is from 1 to 12.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub expire_month: Option<i32>,
     /// The four-digit expiration year.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub expire_year: Option<i32>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RequestTrackersBatch<'a> {
+    pub trackers: &'a [Tracker],
+}
+
+#[derive(Serialize, Debug)]
+pub struct RequestRefund<'a, A> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<&'a A>,
+}
+
+/// Shipment tracking information attached to a PayPal transaction.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Tracker {
+    /// The PayPal transaction ID.
+    pub transaction_id: String,
+    /// The tracking number for the shipment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracking_number: Option<String>,
+    /// The status of the item shipment.
+    pub status: TrackerStatus,
+    /// The carrier for the shipment. Required when a tracking number is provided.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub carrier: Option<Carrier>,
+    /// The name of the carrier, required when `carrier` is PayPal's `OTHER` code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub carrier_name_other: Option<String>,
+    /// The date when the shipment occurred.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shipment_date: Option<chr::NaiveDate>,
+    /// If true, PayPal sends an email notification to the buyer of the transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify_buyer: Option<bool>,
+    /// An array of request-related
+    /// [HATEOAS links](https://developer.paypal.com/docs/api/reference/api-responses/#hateoas-links).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+string_enum! {
+    /// The status of a shipment.
+    pub enum TrackerStatus {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// The shipment was cancelled and the tracking number no longer applies.
+        Cancelled = "CANCELLED",
+        /// The item was already delivered when the tracking number was uploaded.
+        Delivered = "DELIVERED",
+        /// Either the buyer physically picked up the item or the seller delivered the item in
+        /// person without involving any couriers or postal companies.
+        LocalPickup = "LOCAL_PICKUP",
+        /// The item is on hold. Its shipment was temporarily stopped due to bad weather, a strike,
+        /// customs, or another reason.
+        OnHold = "ON_HOLD",
+        /// The item was shipped and is on the way.
+        Shipped = "SHIPPED",
+        /// A shipment was created but not yet handed to the carrier.
+        ShipmentCreated = "SHIPMENT_CREATED",
+        /// The item was dropped off at the carrier.
+        DroppedOff = "DROPPED_OFF",
+        /// The item is in transit to the buyer.
+        InTransit = "IN_TRANSIT",
+        /// The item was returned to the seller.
+        Returned = "RETURNED",
+        /// The shipping label was printed.
+        LabelPrinted = "LABEL_PRINTED",
+        /// The carrier reported an error for the shipment.
+        Error = "ERROR",
+        /// The shipment is not yet confirmed by the carrier.
+        Unconfirmed = "UNCONFIRMED",
+        /// The carrier failed to pick the item up.
+        PickupFailed = "PICKUP_FAILED",
+        /// The delivery is delayed.
+        DeliveryDelayed = "DELIVERY_DELAYED",
+        /// The delivery is scheduled.
+        DeliveryScheduled = "DELIVERY_SCHEDULED",
+        /// The delivery failed.
+        DeliveryFailed = "DELIVERY_FAILED",
+        /// The item is being returned to the seller.
+        InReturn = "INRETURN",
+        /// The shipment is being processed.
+        InProcess = "IN_PROCESS",
+        /// The shipment is new.
+        New = "NEW",
+        /// The shipment was voided.
+        Void = "VOID",
+        /// The shipment was processed.
+        Processed = "PROCESSED",
+        /// The item was not shipped.
+        NotShipped = "NOT_SHIPPED",
+    }
+}
+
+string_enum! {
+    /// A shipping carrier, identified by its PayPal carrier code.
+    ///
+    /// Only the most common carriers are listed; any other code PayPal accepts, including its own
+    /// `OTHER` code used together with `carrier_name_other`, round-trips through `Other`.
+    pub enum Carrier {
+        /// Any other PayPal carrier code.
+        Other(String),
+        /// United Parcel Service.
+        Ups = "UPS",
+        /// United States Postal Service.
+        Usps = "USPS",
+        /// FedEx.
+        Fedex = "FEDEX",
+        /// DHL Express.
+        Dhl = "DHL",
+        /// DHL, tracked through DHL's API.
+        DhlApi = "DHL_API",
+        /// DHL eCommerce.
+        DhlGlobalEcommerce = "DHL_GLOBAL_ECOMMERCE",
+        /// DHL Global Mail.
+        DhlGlobalMail = "DHL_GLOBAL_MAIL",
+        /// DHL Packet.
+        DhlPacket = "DHL_PACKET",
+        /// DHL Deutsche Post.
+        DhlDeutschePost = "DHL_DEUTSCHE_POST",
+        /// DPD.
+        Dpd = "DPD",
+        /// GLS.
+        Gls = "GLS",
+        /// TNT.
+        Tnt = "TNT",
+        /// Aramex.
+        Aramex = "ARAMEX",
+        /// SF Express.
+        SfExpress = "SF_EXPRESS",
+        /// OnTrac.
+        Ontrac = "ONTRAC",
+        /// LaserShip.
+        Lasership = "LASERSHIP",
+        /// Canada Post.
+        CanadaPost = "CANADA_POST",
+        /// Royal Mail.
+        RoyalMail = "ROYAL_MAIL",
+        /// Australia Post.
+        AustraliaPost = "AUSTRALIA_POST",
+    }
+}
+
+/// Identifies a tracker that was added in a batch.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TrackerIdentifier {
+    /// The PayPal transaction ID.
+    pub transaction_id: String,
+    /// The tracking number for the shipment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracking_number: Option<String>,
+    /// An array of request-related HATEOAS links.
+    #[serde(default)]
+    pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Returned when adding trackers in a batch.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TrackersBatchResponse {
+    /// The trackers that were added.
+    #[serde(default)]
+    pub tracker_identifiers: Vec<TrackerIdentifier>,
+    /// The trackers that PayPal rejected, one error per tracker.
+    #[serde(default)]
+    pub errors: Vec<BatchError>,
+    /// An array of request-related HATEOAS links.
+    #[serde(default)]
+    pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Describes why a single item of a batch request failed.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchError {
+    /// The human-readable, unique name of the error.
+    pub name: String,
+    /// The message that describes the error.
+    pub message: String,
+    /// The PayPal internal ID, used for correlation purposes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debug_id: Option<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Tracking information for a captured v2 order.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderTracker {
+    /// The PayPal capture ID the shipment belongs to.
+    pub capture_id: String,
+    /// The tracking number for the shipment.
+    pub tracking_number: String,
+    /// The carrier for the shipment.
+    pub carrier: Carrier,
+    /// The name of the carrier, required when `carrier` is PayPal's `OTHER` code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub carrier_name_other: Option<String>,
+    /// If true, PayPal sends an email notification to the payer.
+    pub notify_payer: bool,
+}
+
+/// A v2 checkout order. Only the fields every order response carries are modelled.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Order {
+    /// The ID of the order.
+    pub id: String,
+    /// The order status.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<OrderStatus>,
+    /// What the payer buys, with the captures and refunds of each purchase unit once there are
+    /// any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub purchase_units: Vec<PurchaseUnit>,
+    /// An array of request-related HATEOAS links.
+    #[serde(default)]
+    pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Order {
+    /// Finds the link with the given relation type.
+    pub fn link(&self, rel: &LinkRel) -> Option<&LinkDescription> {
+        find_link(&self.links, rel)
+    }
+
+    /// The URL to redirect the payer to, so that they approve the order or complete the action
+    /// PayPal requires from them.
+    pub fn approval_url(&self) -> Option<&str> {
+        self.link(&LinkRel::Approve)
+            .or_else(|| self.link(&LinkRel::PayerAction))
+            .map(|link| link.href.as_str())
+    }
+}
+
+/// The body of a request creating a v2 order. See `order::create`.
+#[derive(Serialize, Debug)]
+pub struct OrderRequest {
+    /// Whether the payment is captured or only authorized once the payer approves the order.
+    pub intent: OrderIntent,
+    /// What the payer buys, with one purchase unit per merchant.
+    pub purchase_units: Vec<PurchaseUnit>,
+    /// Where the payer goes once they approve or cancel the order, and how the PayPal pages
+    /// look.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_context: Option<OrderApplicationContext>,
+}
+
+/// Customizes the approval flow of a v2 order.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct OrderApplicationContext {
+    /// Where PayPal sends the payer once they approve the order, with the order ID as the
+    /// `token` query parameter and their payer ID as `PayerID`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_url: Option<String>,
+    /// Where PayPal sends the payer if they cancel, with the order ID as the `token` query
+    /// parameter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancel_url: Option<String>,
+    /// A label that overrides the business name in the merchant's PayPal account on the PayPal
+    /// pages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brand_name: Option<String>,
+    /// Any other fields to send, such as `locale` or `user_action`.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+string_enum! {
+    /// What happens to the payment of a v2 order once the payer approves it.
+    pub enum OrderIntent {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// The merchant captures the payment immediately after the payer approves it.
+        Capture = "CAPTURE",
+        /// The merchant authorizes the payment and captures it later.
+        Authorize = "AUTHORIZE",
+    }
+}
+
+/// What a payer buys from a single merchant in a v2 order.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PurchaseUnit {
+    /// The API caller-provided ID of the purchase unit. Required when an order has more than one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_id: Option<String>,
+    /// The total amount of the purchase unit.
+    #[serde(default)]
+    pub amount: Money,
+    /// The merchant who receives the funds. Defaults to the API caller, a partner sets it to act
+    /// on behalf of a connected seller.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payee: Option<OrderPayee>,
+    /// How the funds are split and released, for example the fees a partner keeps.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_instruction: Option<PaymentInstruction>,
+    /// The captures and refunds of the purchase unit. Set by PayPal, leave it empty when
+    /// creating an order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payments: Option<PaymentCollection>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// An amount of money in the v2 APIs.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Money {
+    /// The ISO 4217 currency code, for example "USD" or "EUR".
+    pub currency_code: String,
+    /// The amount, for example "10" or "12.34".
+    pub value: String,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The merchant who receives the funds of a v2 purchase unit or platform fee.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct OrderPayee {
+    /// The email address of the merchant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_address: Option<String>,
+    /// The encrypted PayPal account ID of the merchant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merchant_id: Option<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Instructions for how the funds of a purchase unit are split and released.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PaymentInstruction {
+    /// The fees the partner collects from the payee.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub platform_fees: Vec<PlatformFee>,
+    /// Whether the funds are released to the payee immediately (`INSTANT`) or later on the
+    /// partner's request (`DELAYED`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disbursement_mode: Option<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A fee a partner collects from the payee of a purchase unit.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PlatformFee {
+    /// The amount of the fee.
+    pub amount: Money,
+    /// The merchant who receives the fee. Defaults to the API caller.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payee: Option<OrderPayee>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The payments made for a purchase unit of a v2 order.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PaymentCollection {
+    /// The captured payments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub captures: Vec<Capture>,
+    /// The refunds of captured payments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub refunds: Vec<CaptureRefund>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A payment captured for a v2 order.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Capture {
+    /// The ID of the capture.
+    pub id: String,
+    /// The status of the capture.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<CaptureStatus>,
+    /// The amount captured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Money>,
+    /// Whether no more captures can be made for the authorization.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_capture: Option<bool>,
+    /// The date and time when the capture was made.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<chr::DateTime<chr::Utc>>,
+    /// An array of request-related HATEOAS links.
+    #[serde(default)]
+    pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+string_enum! {
+    /// The status of a captured payment.
+    pub enum CaptureStatus {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// The funds were credited to the payee's PayPal account.
+        Completed = "COMPLETED",
+        /// The funds could not be captured.
+        Declined = "DECLINED",
+        /// Part of the captured amount was refunded to the payer.
+        PartiallyRefunded = "PARTIALLY_REFUNDED",
+        /// The funds were not yet credited, for example while PayPal reviews the payment.
+        Pending = "PENDING",
+        /// The whole captured amount was refunded to the payer.
+        Refunded = "REFUNDED",
+        /// The funds could not be captured.
+        Failed = "FAILED",
+    }
+}
+
+/// A refund of a payment captured for a v2 order.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CaptureRefund {
+    /// The ID of the refund.
+    pub id: String,
+    /// The status of the refund.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<RefundStatus>,
+    /// The amount refunded to the payer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<Money>,
+    /// The date and time when the refund was made.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<chr::DateTime<chr::Utc>>,
+    /// An array of request-related HATEOAS links.
+    #[serde(default)]
+    pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+string_enum! {
+    /// The status of a refund of a v2 capture.
+    pub enum RefundStatus {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// The refund was cancelled.
+        Cancelled = "CANCELLED",
+        /// The refund could not be processed.
+        Failed = "FAILED",
+        /// The refund is being processed.
+        Pending = "PENDING",
+        /// The funds were returned to the payer.
+        Completed = "COMPLETED",
+    }
+}
+
+string_enum! {
+    /// The status of a v2 order.
+    pub enum OrderStatus {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// The order was created with the specified context.
+        Created = "CREATED",
+        /// The order was saved and persisted.
+        Saved = "SAVED",
+        /// The customer approved the payment.
+        Approved = "APPROVED",
+        /// All purchase units in the order are voided.
+        Voided = "VOIDED",
+        /// The payment was authorized or the authorized payment was captured for the order.
+        Completed = "COMPLETED",
+        /// The order requires an action from the payer, such as 3D Secure authentication.
+        PayerActionRequired = "PAYER_ACTION_REQUIRED",
+    }
+}
+
+/// A single [JSON Patch](https://tools.ietf.org/html/rfc6902) operation, as accepted by PayPal's
+/// PATCH endpoints.
+///
+/// ```rust
+/// use paypal::{PatchOp, PatchPath, TransactionAmount};
+///
+/// let amount = TransactionAmount {
+///     currency: "USD".to_string(),
+///     total: "25.00".to_string(),
+///     ..Default::default()
+/// };
+/// let ops = vec![
+///     PatchOp::replace(PatchPath::root().field("transactions").index(0).field("amount"), &amount)?,
+///     PatchOp::remove("/transactions/0/item_list/shipping_address"),
+/// ];
+/// # Ok::<(), paypal::error::Error>(())
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PatchOp {
+    /// The operation to perform.
+    pub op: PatchOperation,
+    /// The JSON Pointer to the target document location at which to complete the operation.
+    pub path: String,
+    /// The value to apply. The remove operation does not require a value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+}
+
+impl PatchOp {
+    /// Adds `value` at `path`, or replaces the value already there. Fails if `value` cannot be
+    /// represented as JSON, for example a map with non-string keys.
+    pub fn add<P: Into<PatchPath>, V: Serialize + ?Sized>(path: P, value: &V) -> Resp<Self> {
+        Self::with_value(PatchOperation::Add, path.into(), value)
+    }
+
+    /// Replaces the value at `path` with `value`. Fails if `value` cannot be represented as
+    /// JSON, for example a map with non-string keys.
+    pub fn replace<P: Into<PatchPath>, V: Serialize + ?Sized>(path: P, value: &V) -> Resp<Self> {
+        Self::with_value(PatchOperation::Replace, path.into(), value)
+    }
+
+    /// Removes the value at `path`.
+    pub fn remove<P: Into<PatchPath>>(path: P) -> Self {
+        Self {
+            op: PatchOperation::Remove,
+            path: path.into().0,
+            value: None,
+        }
+    }
+
+    fn with_value<V: Serialize + ?Sized>(
+        op: PatchOperation,
+        path: PatchPath,
+        value: &V,
+    ) -> Resp<Self> {
+        Ok(Self {
+            op,
+            path: path.0,
+            value: Some(serde_json::to_value(value)?),
+        })
+    }
+}
+
+/// The kind of a JSON Patch operation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PatchOperation {
+    /// Adds a value, or replaces it if it already exists.
+    Add,
+    /// Removes the value at the target location.
+    Remove,
+    /// Replaces the value at the target location.
+    Replace,
+}
+
+/// The location a `PatchOp` applies to, built a segment at a time so that field names are
+/// escaped as JSON Pointer requires. A `&str` converts into a path as it is, for paths written
+/// out in full.
+///
+/// ```rust
+/// use paypal::PatchPath;
+///
+/// let path = PatchPath::root()
+///     .field("purchase_units")
+///     .filter("reference_id", "default")?
+///     .field("shipping")
+///     .field("address");
+/// assert_eq!("/purchase_units/@reference_id=='default'/shipping/address", path.as_str());
+/// # Ok::<(), paypal::error::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PatchPath(String);
+
+impl PatchPath {
+    /// The whole document.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// The field `name` of the current location.
+    pub fn field(mut self, name: &str) -> Self {
+        self.0.push('/');
+        self.0.push_str(&escape(name));
+        self
+    }
+
+    /// The element at `index` of the current array.
+    pub fn index(mut self, index: usize) -> Self {
+        self.0.push('/');
+        self.0.push_str(&index.to_string());
+        self
+    }
+
+    /// The element of the current array whose `field` equals `value`, in PayPal's filter
+    /// syntax, such as `@reference_id=='default'`. The syntax has no way to quote a quote, so
+    /// this fails if either contains one.
+    pub fn filter(mut self, field: &str, value: &str) -> Resp<Self> {
+        if let Some(quoted) = [field, value].iter().find(|s| s.contains(['\'', '"'])) {
+            return Err(Error::new(format!(
+                "cannot filter on {:?}: patch filters cannot contain quotes",
+                quoted
+            )));
+        }
+        self.0
+            .push_str(&format!("/@{}=='{}'", escape(field), escape(value)));
+        Ok(self)
+    }
+
+    /// The end of the current array, where `PatchOp::add` appends.
+    pub fn end(mut self) -> Self {
+        self.0.push_str("/-");
+        self
+    }
+
+    /// The path as a JSON Pointer.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// as JSON Pointer requires of each segment
+fn escape(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+impl From<&str> for PatchPath {
+    fn from(path: &str) -> Self {
+        PatchPath(path.into())
+    }
+}
+
+impl fmt::Display for PatchPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A payment experience web profile, used to customize the PayPal checkout pages.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct WebProfile {
+    /// The ID of the web experience profile. Assigned by PayPal, leave it empty when creating one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// The web experience profile name. Unique for a specified merchant's profiles.
+    pub name: String,
+    /// Indicates whether the profile persists for three hours or permanently. Set to `false` to
+    /// persist the profile permanently.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temporary: Option<bool>,
+    /// Parameters for the flow configuration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flow_config: Option<FlowConfig>,
+    /// Parameters for the input fields customization.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_fields: Option<InputFields>,
+    /// Parameters for the style and presentation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation: Option<Presentation>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The web experience profile PayPal created, as returned by `web_profile::create`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreatedWebProfile {
+    /// The ID of the new profile, to pass to `RequestNewPayment::with_experience_profile`.
+    pub id: String,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Parameters for the flow configuration of a web profile.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct FlowConfig {
+    /// The type of landing page to display on the PayPal site for user checkout. Set to `Billing`
+    /// to use the non-PayPal account landing page or to `Login` to use the PayPal account login
+    /// landing page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub landing_page_type: Option<String>,
+    /// The merchant site URL to display after a bank transfer payment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bank_txn_pending_url: Option<String>,
+    /// Set to `commit` to show a Pay Now button on the PayPal checkout page instead of Continue.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_action: Option<String>,
+    /// The HTTP method, `GET` or `POST`, to use to redirect the payer to the return URL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_uri_http_method: Option<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Parameters for the input fields customization of a web profile.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct InputFields {
+    /// Indicates whether the buyer can enter a note to the merchant on the PayPal page during
+    /// checkout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_note: Option<bool>,
+    /// Indicates whether PayPal displays shipping address fields on the experience pages. `0`
+    /// displays them, `1` redacts them and `2` gets the address from the buyer's account profile.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_shipping: Option<u8>,
+    /// Indicates whether to display the shipping address that is passed to this call rather than
+    /// the one on file with PayPal. `0` displays the address on file, `1` the one passed in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address_override: Option<u8>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Parameters for the style and presentation of a web profile.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Presentation {
+    /// A label that overrides the business name in the PayPal account on the PayPal pages.
+    /// Maximum length is 127 characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brand_name: Option<String>,
+    /// A URL to the logo image. A valid media type is .gif, .jpg, or .png. The maximum width of
+    /// the image is 190 pixels and the maximum height is 60 pixels.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logo_image: Option<String>,
+    /// The locale of pages displayed by PayPal payment experience, for example `US` or `fr_FR`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale_code: Option<String>,
+    /// A label to use as hypertext for the return to merchant link.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_url_label: Option<String>,
+    /// A label to use as the title for the note to seller field. Used only when
+    /// `InputFields::allow_note` is `true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note_to_seller_label: Option<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The data of a partner referral, used to onboard a seller as a PayPal partner.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PartnerReferral {
+    /// The partner's unique identifier for this seller, used to look the seller up later.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracking_id: Option<String>,
+    /// The email address of the seller, used to prefill the sign-up form.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// The preferred language of the onboarding flow, for example `en-US`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_language_code: Option<String>,
+    /// The operations the partner wants to perform on behalf of the seller.
+    pub operations: Vec<ReferralOperation>,
+    /// The PayPal products the seller is onboarded to, for example `EXPRESS_CHECKOUT` or `PPCP`.
+    pub products: Vec<String>,
+    /// The consents the seller gives the partner.
+    pub legal_consents: Vec<LegalConsent>,
+    /// Overrides the partner configuration for this referral.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partner_config_override: Option<PartnerConfigOverride>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// An operation the partner wants to perform on behalf of a referred seller.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReferralOperation {
+    /// The operation, usually `API_INTEGRATION`.
+    pub operation: String,
+    /// How the partner integrates with PayPal's APIs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_integration_preference: Option<IntegrationPreference>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// How the partner integrates with PayPal's APIs.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IntegrationPreference {
+    /// The REST API integration details.
+    pub rest_api_integration: RestApiIntegration,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The REST API integration details of a partner.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RestApiIntegration {
+    /// The integration method, `PAYPAL` or `BRAINTREE`.
+    pub integration_method: String,
+    /// The integration type, `FIRST_PARTY` or `THIRD_PARTY`.
+    pub integration_type: String,
+    /// The features the partner may use for the seller. Required for `THIRD_PARTY` integrations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub third_party_details: Option<ThirdPartyDetails>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The features a third-party partner may use on behalf of the seller.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ThirdPartyDetails {
+    /// The features, for example `PAYMENT`, `REFUND` or `PARTNER_FEE`.
+    pub features: Vec<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A consent the seller gives the partner.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct LegalConsent {
+    /// The type of consent, for example `SHARE_DATA_CONSENT`.
+    #[serde(rename = "type")]
+    pub _type: String,
+    /// Whether the seller granted the consent.
+    pub granted: bool,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Overrides the partner configuration for a single referral.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PartnerConfigOverride {
+    /// The URL where the seller is redirected after completing the onboarding flow.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_url: Option<String>,
+    /// A description of the return URL, shown to the seller.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_url_description: Option<String>,
+    /// The URL of the partner's logo, shown during the onboarding flow.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partner_logo_url: Option<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Returned when creating a partner referral. The `action_url` link is where the seller signs up.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PartnerReferralLinks {
+    /// An array of request-related HATEOAS links.
+    pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A previously created partner referral.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PartnerReferralDetails {
+    /// The ID of the partner referral.
+    pub partner_referral_id: String,
+    /// The payer ID of the partner who submitted the referral.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submitter_payer_id: Option<String>,
+    /// The data that was submitted with the referral.
+    pub referral_data: PartnerReferral,
+    /// An array of request-related HATEOAS links.
+    #[serde(default)]
+    pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The integration status of a seller connected to a partner.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MerchantIntegration {
+    /// The payer ID of the seller.
+    pub merchant_id: String,
+    /// The partner's unique identifier for this seller.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracking_id: Option<String>,
+    /// The products the seller was onboarded to.
+    #[serde(default)]
+    pub products: Vec<MerchantProduct>,
+    /// The capabilities of the seller's account.
+    #[serde(default)]
+    pub capabilities: Vec<MerchantCapability>,
+    /// Whether the seller's account can receive payments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payments_receivable: Option<bool>,
+    /// Whether the seller confirmed their primary email address.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_email_confirmed: Option<bool>,
+    /// An array of request-related HATEOAS links.
+    #[serde(default)]
+    pub links: Vec<LinkDescription>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A product a seller was onboarded to.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MerchantProduct {
+    /// The name of the product.
+    pub name: String,
+    /// The vetting status of the product, for example `SUBSCRIBED` or `NEED_MORE_DATA`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vetting_status: Option<String>,
+    /// The capabilities the product grants.
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A capability of a seller's account.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MerchantCapability {
+    /// The name of the capability, for example `CUSTOM_CARD_PROCESSING`.
+    pub name: String,
+    /// The status of the capability, for example `ACTIVE`.
+    pub status: String,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The body of a request asking PayPal whether a webhook event was really sent by PayPal. See
+/// `webhook::verify`.
+#[derive(Serialize, Debug)]
+pub struct WebhookSignature {
+    /// The algorithm of the signature, from the `PAYPAL-AUTH-ALGO` header.
+    pub auth_algo: String,
+    /// The URL of the certificate that signed the event, from the `PAYPAL-CERT-URL` header.
+    pub cert_url: String,
+    /// The ID of the transmission, from the `PAYPAL-TRANSMISSION-ID` header.
+    pub transmission_id: String,
+    /// The signature, from the `PAYPAL-TRANSMISSION-SIG` header.
+    pub transmission_sig: String,
+    /// When the event was sent, from the `PAYPAL-TRANSMISSION-TIME` header.
+    pub transmission_time: String,
+    /// The ID PayPal gave the webhook when it was registered.
+    pub webhook_id: String,
+    /// The body of the webhook event, as received.
+    pub webhook_event: serde_json::Value,
+}
+
+impl WebhookSignature {
+    /// Takes the signature from the `PAYPAL-*` headers of the request that delivered
+    /// `webhook_event`, whatever their case.
+    pub fn from_headers(
+        headers: &HashMap<String, String>,
+        webhook_id: &str,
+        webhook_event: serde_json::Value,
+    ) -> Result<WebhookSignature, Error> {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+                .ok_or_else(|| Error::new(format!("the {} header is missing", name)))
+        };
+        Ok(WebhookSignature {
+            auth_algo: header("PAYPAL-AUTH-ALGO")?,
+            cert_url: header("PAYPAL-CERT-URL")?,
+            transmission_id: header("PAYPAL-TRANSMISSION-ID")?,
+            transmission_sig: header("PAYPAL-TRANSMISSION-SIG")?,
+            transmission_time: header("PAYPAL-TRANSMISSION-TIME")?,
+            webhook_id: webhook_id.to_string(),
+            webhook_event,
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WebhookVerification {
+    pub verification_status: VerificationStatus,
+}
+
+string_enum! {
+    /// Whether PayPal confirmed the signature of a webhook event.
+    pub enum VerificationStatus {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// The event was sent by PayPal.
+        Success = "SUCCESS",
+        /// The signature does not match, so the event must not be trusted.
+        Failure = "FAILURE",
+    }
+}
+
+/// Which transactions `transaction::search` looks for. PayPal only searches 31 days at a time.
+#[derive(Clone, Debug)]
+pub struct TransactionSearch {
+    /// The start of the period to search, inclusive.
+    pub start_date: chr::DateTime<chr::Utc>,
+    /// The end of the period to search, inclusive.
+    pub end_date: chr::DateTime<chr::Utc>,
+    /// Only finds the transaction with this ID.
+    pub transaction_id: Option<String>,
+    /// Only finds transactions with this status.
+    pub transaction_status: Option<TransactionStatus>,
+}
+
+impl TransactionSearch {
+    /// Searches every transaction between `start_date` and `end_date`.
+    pub fn new(
+        start_date: chr::DateTime<chr::Utc>,
+        end_date: chr::DateTime<chr::Utc>,
+    ) -> TransactionSearch {
+        TransactionSearch {
+            start_date,
+            end_date,
+            transaction_id: None,
+            transaction_status: None,
+        }
+    }
+}
+
+/// One transaction found by `transaction::search`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TransactionDetails {
+    /// The transaction itself.
+    pub transaction_info: TransactionInfo,
+    /// Any fields PayPal sent that are not modelled above, such as the payer or cart info.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The transaction information of a `TransactionDetails`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TransactionInfo {
+    /// The ID of the transaction, such as the ID of a sale, capture or refund.
+    pub transaction_id: String,
+    /// The ID of the transaction this one refers to, such as the capture a refund returns.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paypal_reference_id: Option<String>,
+    /// PayPal's code for the kind of transaction, for example `T0006` for a checkout payment or
+    /// `T1107` for a refund.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_event_code: Option<String>,
+    /// When the transaction started.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_initiation_date: Option<chr::DateTime<chr::Utc>>,
+    /// When the transaction was last updated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_updated_date: Option<chr::DateTime<chr::Utc>>,
+    /// The gross amount of the transaction, negative for money leaving the account.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_amount: Option<Money>,
+    /// The fee PayPal charged for the transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_amount: Option<Money>,
+    /// The status of the transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_status: Option<TransactionStatus>,
+    /// The invoice ID the merchant gave the transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invoice_id: Option<String>,
+    /// The custom field the merchant gave the transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_field: Option<String>,
+    /// Any fields PayPal sent that are not modelled above.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+string_enum! {
+    /// The status of a transaction found by `transaction::search`.
+    pub enum TransactionStatus {
+        /// A value this version of the crate does not know about.
+        Unknown(String),
+        /// PayPal or the merchant rejected the transaction.
+        Denied = "D",
+        /// The transaction is pending, for example while PayPal reviews it.
+        Pending = "P",
+        /// The transaction completed successfully.
+        Success = "S",
+        /// The transaction was reversed, for example by a refund or chargeback.
+        Reversed = "V",
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TransactionSearchResponse {
+    #[serde(default)]
+    pub transaction_details: Vec<TransactionDetails>,
+    #[serde(default)]
+    pub page: u32,
+    #[serde(default)]
+    pub total_pages: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scopes() {
+        let token: ResponseAccessToken = serde_json::from_value(serde_json::json!({
+            "scope": "openid https://api.paypal.com/v1/payments/.* https://uri.paypal.com/x",
+            "nonce": "2020-04-03T15:35:36ZaYZlGvEkV4yVSz8g6bAKFoGSEzuy3CQcz3ljhibkOHg",
+            "access_token": "A21AAF",
+            "token_type": "Bearer",
+            "app_id": "APP-80W284485P519543T",
+            "expires_in": 32400
+        }))
+        .unwrap();
+        assert!(token.scope.contains(&Scope::Payments));
+        assert!(!token.scope.contains(&Scope::Refund));
+        assert_eq!(
+            Some(&Scope::Unknown("https://uri.paypal.com/x".into())),
+            token.scope.iter().next_back()
+        );
+        assert_eq!(None, token.expires_at());
+        let received_at = chr::Utc::now() - chr::Duration::hours(1);
+        let token = ResponseAccessToken {
+            received_at: Some(received_at),
+            ..token
+        };
+        let cached: ResponseAccessToken =
+            serde_json::from_str(&serde_json::to_string(&token).unwrap()).unwrap();
+        assert_eq!(
+            Some(received_at + chr::Duration::hours(9)),
+            cached.expires_at()
+        );
+        assert_eq!(
+            &[Scope::Payments, Scope::Payment, Scope::FuturePayments],
+            Scope::accepted_for("/v1/payments/payment/{id}")
+        );
+        assert_eq!(
+            &[Scope::Payments, Scope::Refund],
+            Scope::accepted_for("/v1/payments/sale/{id}/refund")
+        );
+        assert!(Scope::accepted_for("/v1/identity/oauth2/userinfo").is_empty());
+    }
+
+    fn round_trip<T>(json: &str) -> T
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let value: T = serde_json::from_str(json).unwrap();
+        assert_eq!(json, serde_json::to_string(&value).unwrap());
+        value
+    }
+
+    #[test]
+    fn test_known_values_round_trip() {
+        assert_eq!(State::Canceled, round_trip("\"canceled\""));
+        assert_eq!(
+            PaymentMethod::PayUponInvoice,
+            round_trip("\"pay_upon_invoice\"")
+        );
+        assert_eq!(PayerStatus::Verified, round_trip("\"VERIFIED\""));
+        assert_eq!(
+            FailureReason::PayerCannotPay,
+            round_trip("\"PAYER_CANNOT_PAY\"")
+        );
+        assert_eq!(
+            NormalizationStatus::UnnormalizedUserPreferred,
+            round_trip("\"UNNORMALIZED_USER_PREFERRED\"")
+        );
+        assert_eq!(
+            OrderStatus::PayerActionRequired,
+            round_trip("\"PAYER_ACTION_REQUIRED\"")
+        );
+        assert_eq!(NormalizationStatus::Unknown, round_trip("\"UNKNOWN\""));
+        assert_eq!(
+            State::PartiallyRefunded,
+            round_trip("\"partially_refunded\"")
+        );
+        assert_eq!(CardBrand::Amex, round_trip("\"amex\""));
+        assert_eq!(CountryCode::UnitedKingdom, round_trip("\"GB\""));
+        assert_eq!(CountryCode::ChinaWorldwide, round_trip("\"C2\""));
+    }
+
+    #[test]
+    fn test_unknown_values_round_trip() {
+        assert_eq!(
+            State::Unknown("in_progress".into()),
+            round_trip("\"in_progress\"")
+        );
+        assert_eq!(
+            PaymentMethod::Unknown("venmo".into()),
+            round_trip("\"venmo\"")
+        );
+        assert_eq!(
+            NormalizationStatus::Other("PARTIALLY_NORMALIZED".into()),
+            round_trip("\"PARTIALLY_NORMALIZED\"")
+        );
+        assert_eq!(
+            TrackerStatus::Unknown("LOST".into()),
+            round_trip("\"LOST\"")
+        );
+    }
+
+    #[test]
+    fn test_payment_with_unknown_state() {
+        let payment: Payment = serde_json::from_str(
+            r#"{
+                "id": "PAYID-LZ7ZYRA",
+                "intent": "sale",
+                "payer": { "payment_method": "paypal", "status": "PENDING_VERIFICATION" },
+                "transactions": [],
+                "state": "in_flux",
+                "links": []
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(Some(State::Unknown("in_flux".into())), payment.state);
+    }
+
+    #[test]
+    fn test_payment_round_trip_is_lossless() {
+        let json = serde_json::json!({
+            "id": "PAYID-LZ7ZYRA",
+            "intent": "sale",
+            "cart": "4NB02386DS4263216",
+            "payer": {
+                "payment_method": "paypal",
+                "status": "VERIFIED",
+                "payer_info": { "email": "buyer@example.com", "payer_id": "QYR5Z8XDVJNXQ" }
+            },
+            "transactions": [{
+                "amount": {
+                    "currency": "USD",
+                    "total": "30.11",
+                    "details": { "subtotal": "30.00", "tax": "0.07", "shipping": "0.03" }
+                },
+                "description": "The payment transaction description.",
+                "related_resources": []
+            }],
+            "state": "approved",
+            "create_time": "2019-10-01T12:13:14Z",
+            "links": [{
+                "href": "https://api.sandbox.paypal.com/v1/payments/payment/PAYID-LZ7ZYRA",
+                "rel": "self",
+                "method": "GET"
+            }]
+        });
+        let payment: Payment = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!("4NB02386DS4263216", payment.extra["cart"]);
+        assert_eq!(json, serde_json::to_value(&payment).unwrap());
+    }
+
+    #[test]
+    fn test_payment_links() {
+        let payment: Payment = serde_json::from_value(serde_json::json!({
+            "id": "PAYID-LZ7ZYRA",
+            "intent": "sale",
+            "payer": { "payment_method": "paypal" },
+            "transactions": [],
+            "links": [
+                { "href": "https://api.sandbox.paypal.com/v1/payments/payment/PAYID-LZ7ZYRA", "rel": "self", "method": "GET" },
+                { "href": "https://www.sandbox.paypal.com/cgi-bin/webscr?cmd=_express-checkout&token=EC-60385559L1062554J", "rel": "approval_url", "method": "REDIRECT" },
+                { "href": "https://api.sandbox.paypal.com/v1/payments/payment/PAYID-LZ7ZYRA/execute", "rel": "execute", "method": "POST" }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            Some("https://www.sandbox.paypal.com/cgi-bin/webscr?cmd=_express-checkout&token=EC-60385559L1062554J"),
+            payment.approval_url()
+        );
+        assert_eq!(
+            Some("POST"),
+            payment
+                .execute_link()
+                .and_then(|link| link.method.as_deref())
+        );
+        assert_eq!(LinkRel::SelfLink, payment.links[0].rel);
+    }
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let card: CreditCard = serde_json::from_value(serde_json::json!({
+            "number": "4417119669820331",
+            "type": "visa",
+            "expire_month": 11,
+            "expire_year": 2028,
+            "cvv2": "874",
+            "links": []
+        }))
+        .unwrap();
+        let debug = format!("{:?}", card);
+        assert!(debug.contains("************0331"));
+        assert!(!debug.contains("4417119669820331"));
+        assert!(!debug.contains("874"));
+
+        let token: ResponseAccessToken = serde_json::from_value(serde_json::json!({
+            "scope": "https://uri.paypal.com/services/payments/payment",
+            "nonce": "2019-09-02T10:09:46ZaR9dSOtWcg",
+            "access_token": "A21AAF",
+            "token_type": "Bearer",
+            "app_id": "APP-80W284485P519543T",
+            "expires_in": 32400
+        }))
+        .unwrap();
+        assert!(!format!("{:?}", token).contains("A21AAF"));
+    }
+
+    #[test]
+    fn test_patch_path() {
+        assert_eq!("", PatchPath::root().as_str());
+        assert_eq!(
+            "/a~1b/m~0n/0/-",
+            PatchPath::root()
+                .field("a/b")
+                .field("m~n")
+                .index(0)
+                .end()
+                .as_str()
+        );
+        assert_eq!(
+            "/items/@sku=='a~1b~0c'/quantity",
+            PatchPath::root()
+                .field("items")
+                .filter("sku", "a/b~c")
+                .unwrap()
+                .field("quantity")
+                .as_str()
+        );
+        assert!(PatchPath::root().filter("sku", "it's").is_err());
+        assert!(PatchPath::root().filter("sku", "a\"b").is_err());
+        assert!(PatchPath::root().filter("it's", "sku").is_err());
+    }
+
+    #[test]
+    fn test_patch_op() {
+        assert_eq!(
+            r#"{"op":"add","path":"/items/-","value":{"sku":"a"}}"#,
+            serde_json::to_string(
+                &PatchOp::add(
+                    PatchPath::root().field("items").end(),
+                    &serde_json::json!({"sku": "a"})
+                )
+                .unwrap()
+            )
+            .unwrap()
+        );
+        let mut keys = std::collections::HashMap::new();
+        keys.insert((1, 2), "not a string key");
+        assert!(PatchOp::add("/metadata", &keys).is_err());
+        assert!(PatchOp::replace("/metadata", &keys).is_err());
+    }
 }
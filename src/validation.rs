@@ -0,0 +1,606 @@
+use crate::error::Error;
+use crate::types::{Address, CardBrand, CountryCode, CreditCard};
+use chrono::{Datelike, NaiveDate, Utc};
+use std::fmt;
+
+/// What is wrong with a field that failed validation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Problem {
+    /// The value is required but missing or empty.
+    Required,
+    /// The value must consist of digits only.
+    NotNumeric,
+    /// The value has the wrong number of characters.
+    InvalidLength,
+    /// The card number fails the Luhn checksum, usually because of a typo.
+    Checksum,
+    /// The card number does not belong to any brand PayPal accepts.
+    UnknownBrand,
+    /// The brand is not one PayPal accepts.
+    UnsupportedBrand,
+    /// The brand does not match the card number, which belongs to the given brand.
+    BrandMismatch(CardBrand),
+    /// The value is outside of the allowed range.
+    OutOfRange,
+    /// The card expired.
+    Expired,
+    /// The value is longer than the given number of characters.
+    TooLong(usize),
+    /// The value is not in the format the field requires.
+    Format,
+    /// The country code is not an ISO 3166-1 code.
+    UnknownCountry,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::Required => f.write_str("is required"),
+            Problem::NotNumeric => f.write_str("must contain digits only"),
+            Problem::InvalidLength => f.write_str("has the wrong length"),
+            Problem::Checksum => f.write_str("fails the checksum"),
+            Problem::UnknownBrand => f.write_str("does not belong to a supported card brand"),
+            Problem::UnsupportedBrand => f.write_str("is not a supported card brand"),
+            Problem::BrandMismatch(brand) => write!(f, "does not match the {} card number", brand),
+            Problem::OutOfRange => f.write_str("is out of range"),
+            Problem::Expired => f.write_str("is in the past"),
+            Problem::TooLong(max) => write!(f, "is longer than {} characters", max),
+            Problem::Format => f.write_str("is not in the expected format"),
+            Problem::UnknownCountry => f.write_str("is not an ISO 3166-1 country code"),
+        }
+    }
+}
+
+/// A field that failed validation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    /// The field, named as in PayPal's JSON, for example `number` or `billing_address.state`.
+    pub field: String,
+    /// What is wrong with it.
+    pub problem: Problem,
+}
+
+/// Every field that failed validation. Converts into an `Error`, so that it can be returned
+/// with `?` next to API calls.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationErrors {
+    errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    /// The fields that failed validation, in the order they were checked.
+    pub fn errors(&self) -> &[FieldError] {
+        &self.errors
+    }
+
+    pub(crate) fn new() -> Self {
+        Self { errors: Vec::new() }
+    }
+
+    pub(crate) fn add(&mut self, field: &str, problem: Problem) {
+        self.errors.push(FieldError {
+            field: field.into(),
+            problem,
+        });
+    }
+
+    // adds the errors of a nested object, such as `billing_address.state`
+    pub(crate) fn nest(&mut self, prefix: &str, nested: Result<(), ValidationErrors>) {
+        if let Err(nested) = nested {
+            self.errors
+                .extend(nested.errors.into_iter().map(|error| FieldError {
+                    field: format!("{}.{}", prefix, error.field),
+                    problem: error.problem,
+                }));
+        }
+    }
+
+    pub(crate) fn into_result(self) -> Result<(), Self> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{} {}", error.field, error.problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl From<ValidationErrors> for Error {
+    fn from(errors: ValidationErrors) -> Self {
+        Error::new(errors.to_string())
+    }
+}
+
+impl CardBrand {
+    /// The brand a card number belongs to, judging by its first digits.
+    pub fn detect(number: &str) -> Option<CardBrand> {
+        let prefix = |len: usize| {
+            number
+                .get(..len)
+                .and_then(|digits| digits.parse::<u32>().ok())
+        };
+        let within = |len: usize, low: u32, high: u32| {
+            prefix(len).is_some_and(|prefix| (low..=high).contains(&prefix))
+        };
+        if within(1, 4, 4) {
+            Some(CardBrand::Visa)
+        } else if within(2, 51, 55) || within(4, 2221, 2720) {
+            Some(CardBrand::Mastercard)
+        } else if within(2, 34, 34) || within(2, 37, 37) {
+            Some(CardBrand::Amex)
+        } else if within(4, 6011, 6011)
+            || within(3, 644, 649)
+            || within(2, 65, 65)
+            || within(6, 622_126, 622_925)
+        {
+            Some(CardBrand::Discover)
+        } else {
+            None
+        }
+    }
+
+    // card number lengths the brand issues
+    fn number_lengths(&self) -> &'static [usize] {
+        match self {
+            CardBrand::Visa => &[13, 16, 19],
+            CardBrand::Mastercard => &[16],
+            CardBrand::Amex => &[15],
+            CardBrand::Discover => &[16, 17, 18, 19],
+            CardBrand::Unknown(_) => &[12, 13, 14, 15, 16, 17, 18, 19],
+        }
+    }
+
+    fn cvv_length(&self) -> usize {
+        match self {
+            CardBrand::Amex => 4,
+            _ => 3,
+        }
+    }
+}
+
+impl CreditCard {
+    /// Checks the card before it is sent to PayPal: the number must pass the Luhn checksum and
+    /// have a length its brand issues, `type` must be the brand of the number, the CVV must have
+    /// the brand's length, the card must not have expired, and the billing address must pass
+    /// `Address::validate`.
+    ///
+    /// ```rust
+    /// use paypal::validation::Problem;
+    /// use paypal::{CardBrand, CreditCard};
+    ///
+    /// let card = CreditCard {
+    ///     number: "4417119669820332".to_string(),
+    ///     _type: CardBrand::Visa,
+    ///     expire_month: 11,
+    ///     expire_year: 2099,
+    ///     cvv2: Some("874".to_string()),
+    ///     first_name: None,
+    ///     last_name: None,
+    ///     billing_address: None,
+    ///     links: Vec::new(),
+    ///     extra: Default::default(),
+    /// };
+    /// let errors = card.validate().unwrap_err();
+    /// assert_eq!(Problem::Checksum, errors.errors()[0].problem);
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        self.validate_on(Utc::now().date_naive())
+    }
+
+    fn validate_on(&self, today: NaiveDate) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let detected = if self.number.is_empty() {
+            errors.add("number", Problem::Required);
+            None
+        } else if !is_numeric(&self.number) {
+            errors.add("number", Problem::NotNumeric);
+            None
+        } else {
+            let detected = CardBrand::detect(&self.number);
+            let lengths = detected.as_ref().unwrap_or(&self._type).number_lengths();
+            if !lengths.contains(&self.number.len()) {
+                errors.add("number", Problem::InvalidLength);
+            } else if !luhn(&self.number) {
+                errors.add("number", Problem::Checksum);
+            }
+            if detected.is_none() {
+                errors.add("number", Problem::UnknownBrand);
+            }
+            detected
+        };
+
+        match (&self._type, detected) {
+            (CardBrand::Unknown(_), _) => errors.add("type", Problem::UnsupportedBrand),
+            (brand, Some(detected)) if *brand != detected => {
+                errors.add("type", Problem::BrandMismatch(detected))
+            }
+            _ => {}
+        }
+
+        if let Some(cvv) = &self.cvv2 {
+            if !is_numeric(cvv) {
+                errors.add("cvv2", Problem::NotNumeric);
+            } else if cvv.len() != self._type.cvv_length() {
+                errors.add("cvv2", Problem::InvalidLength);
+            }
+        }
+
+        if !(1..=12).contains(&self.expire_month) {
+            errors.add("expire_month", Problem::OutOfRange);
+        } else if !(1000..=9999).contains(&self.expire_year) {
+            errors.add("expire_year", Problem::OutOfRange);
+        } else if (self.expire_year, self.expire_month as u32) < (today.year(), today.month()) {
+            // cards stay valid until the end of their expiration month
+            errors.add("expire_year", Problem::Expired);
+        }
+        if let Some(address) = &self.billing_address {
+            errors.nest("billing_address", address.validate());
+        }
+        errors.into_result()
+    }
+}
+
+// countries where PayPal requires the state, as documented on `Address::state`
+const STATE_REQUIRED: &[CountryCode] = &[
+    CountryCode::Argentina,
+    CountryCode::Brazil,
+    CountryCode::Canada,
+    CountryCode::China,
+    CountryCode::India,
+    CountryCode::Italy,
+    CountryCode::Japan,
+    CountryCode::Mexico,
+    CountryCode::Thailand,
+    CountryCode::UnitedStates,
+];
+
+impl Address {
+    /// Checks the address before it is sent to PayPal: `line1` must be set, the country code
+    /// must be an ISO 3166-1 code, the state must be set in the countries PayPal requires it
+    /// for and be at most 40 characters, the postal code must have the format of its country
+    /// for major countries, and the phone number must be in E.123 format and at most 50
+    /// characters.
+    ///
+    /// ```rust
+    /// use paypal::validation::Problem;
+    /// use paypal::{Address, CountryCode};
+    ///
+    /// let address = Address {
+    ///     line1: "2211 N First Street".to_string(),
+    ///     line2: None,
+    ///     city: Some("San Jose".to_string()),
+    ///     country_code: CountryCode::UnitedStates,
+    ///     postal_code: Some("95131".to_string()),
+    ///     state: None,
+    ///     phone: Some("+1 408 967 1000".to_string()),
+    ///     normalization_status: None,
+    ///     _type: None,
+    ///     extra: Default::default(),
+    /// };
+    /// let errors = address.validate().unwrap_err();
+    /// assert_eq!("state", errors.errors()[0].field);
+    /// assert_eq!(Problem::Required, errors.errors()[0].problem);
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if self.line1.trim().is_empty() {
+            errors.add("line1", Problem::Required);
+        }
+        if let CountryCode::Unknown(_) = self.country_code {
+            errors.add("country_code", Problem::UnknownCountry);
+        }
+        match self.state.as_deref().map(str::trim) {
+            None | Some("") if STATE_REQUIRED.contains(&self.country_code) => {
+                errors.add("state", Problem::Required)
+            }
+            Some(state) if state.chars().count() > 40 => errors.add("state", Problem::TooLong(40)),
+            _ => {}
+        }
+        if let Some(postal_code) = &self.postal_code {
+            let formats = postal_code_formats(&self.country_code);
+            if !formats.is_empty() && !formats.iter().any(|f| matches_format(postal_code, f)) {
+                errors.add("postal_code", Problem::Format);
+            }
+        }
+        if let Some(phone) = &self.phone {
+            if phone.chars().count() > 50 {
+                errors.add("phone", Problem::TooLong(50));
+            } else if !is_e123(phone) {
+                errors.add("phone", Problem::Format);
+            }
+        }
+        errors.into_result()
+    }
+}
+
+// `9` stands for a digit, `A` for a letter and a space for an optional space
+fn postal_code_formats(country: &CountryCode) -> &'static [&'static str] {
+    match country {
+        CountryCode::UnitedStates => &["99999", "99999-9999"],
+        CountryCode::Canada => &["A9A 9A9"],
+        CountryCode::UnitedKingdom => &[
+            "A9 9AA", "A99 9AA", "AA9 9AA", "AA99 9AA", "A9A 9AA", "AA9A 9AA",
+        ],
+        CountryCode::Germany
+        | CountryCode::France
+        | CountryCode::Italy
+        | CountryCode::Spain
+        | CountryCode::Mexico => &["99999"],
+        CountryCode::Netherlands => &["9999 AA"],
+        CountryCode::Australia => &["9999"],
+        CountryCode::Japan => &["999-9999", "9999999"],
+        CountryCode::Brazil => &["99999-999", "99999999"],
+        CountryCode::India | CountryCode::China => &["999999"],
+        _ => &[],
+    }
+}
+
+fn matches_format(value: &str, format: &str) -> bool {
+    let mut value = value.trim().chars().peekable();
+    for expected in format.chars() {
+        match expected {
+            ' ' => {
+                value.next_if_eq(&' ');
+            }
+            '9' if value.next_if(char::is_ascii_digit).is_some() => {}
+            'A' if value.next_if(char::is_ascii_alphabetic).is_some() => {}
+            '9' | 'A' => return false,
+            literal if value.next_if_eq(&literal).is_some() => {}
+            _ => return false,
+        }
+    }
+    value.next().is_none()
+}
+
+// E.123 numbers are grouped by single spaces, either international as in `+31 42 123 4567`, or
+// national with the trunk prefix in parentheses as in `(042) 123 4567`
+fn is_e123(phone: &str) -> bool {
+    let (international, number) = match phone.strip_prefix('+') {
+        Some(number) => (true, number),
+        None => (false, phone),
+    };
+    let allowed =
+        |c: char| c.is_ascii_digit() || c == ' ' || (!international && (c == '(' || c == ')'));
+    let digits = number.chars().filter(char::is_ascii_digit).count();
+    number.chars().all(allowed)
+        && (4..=15).contains(&digits)
+        && !number.starts_with(' ')
+        && !number.ends_with(' ')
+        && !number.contains("  ")
+}
+
+fn is_numeric(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+// doubles every second digit from the right, the sum of all digits must be a multiple of 10
+fn luhn(number: &str) -> bool {
+    let sum: u32 = number
+        .bytes()
+        .rev()
+        .map(|b| u32::from(b - b'0'))
+        .enumerate()
+        .map(|(i, digit)| match (i % 2, digit * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => digit,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(number: &str, brand: CardBrand, cvv: &str) -> CreditCard {
+        CreditCard {
+            number: number.into(),
+            _type: brand,
+            expire_month: 11,
+            expire_year: 2028,
+            cvv2: Some(cvv.into()),
+            first_name: None,
+            last_name: None,
+            billing_address: None,
+            links: Vec::new(),
+            extra: Default::default(),
+        }
+    }
+
+    fn problems(card: &CreditCard) -> Vec<(String, Problem)> {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        match card.validate_on(today) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .errors()
+                .iter()
+                .map(|error| (error.field.clone(), error.problem.clone()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Some(CardBrand::Visa), CardBrand::detect("4417119669820331"));
+        assert_eq!(
+            Some(CardBrand::Mastercard),
+            CardBrand::detect("5555555555554444")
+        );
+        assert_eq!(
+            Some(CardBrand::Mastercard),
+            CardBrand::detect("2221000000000009")
+        );
+        assert_eq!(Some(CardBrand::Amex), CardBrand::detect("378282246310005"));
+        assert_eq!(
+            Some(CardBrand::Discover),
+            CardBrand::detect("6011111111111117")
+        );
+        assert_eq!(
+            Some(CardBrand::Discover),
+            CardBrand::detect("6221260000000000")
+        );
+        assert_eq!(None, CardBrand::detect("3530111333300000"));
+    }
+
+    #[test]
+    fn test_luhn() {
+        assert!(luhn("4417119669820331"));
+        assert!(luhn("378282246310005"));
+        assert!(!luhn("4417119669820332"));
+    }
+
+    #[test]
+    fn test_valid_cards() {
+        assert!(problems(&card("4417119669820331", CardBrand::Visa, "874")).is_empty());
+        assert!(problems(&card("378282246310005", CardBrand::Amex, "1234")).is_empty());
+        assert!(problems(&card("5555555555554444", CardBrand::Mastercard, "123")).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_cards() {
+        assert_eq!(
+            vec![("number".to_string(), Problem::Checksum)],
+            problems(&card("4417119669820332", CardBrand::Visa, "874"))
+        );
+        assert_eq!(
+            vec![("number".to_string(), Problem::NotNumeric)],
+            problems(&card("4417 1196 6982 0331", CardBrand::Visa, "874"))
+        );
+        assert_eq!(
+            vec![
+                ("type".to_string(), Problem::BrandMismatch(CardBrand::Amex)),
+                ("cvv2".to_string(), Problem::InvalidLength),
+            ],
+            problems(&card("378282246310005", CardBrand::Visa, "1234"))
+        );
+        assert_eq!(
+            vec![("type".to_string(), Problem::UnsupportedBrand)],
+            problems(&card("4417119669820331", "jcb".into(), "874"))
+        );
+
+        let mut expired = card("4417119669820331", CardBrand::Visa, "874");
+        expired.expire_year = 2026;
+        expired.expire_month = 9;
+        assert_eq!(
+            vec![("expire_year".to_string(), Problem::Expired)],
+            problems(&expired)
+        );
+        expired.expire_month = 10;
+        assert!(problems(&expired).is_empty());
+    }
+
+    fn address(country: CountryCode, state: Option<&str>, postal_code: &str) -> Address {
+        Address {
+            line1: "2211 N First Street".into(),
+            line2: None,
+            city: Some("San Jose".into()),
+            country_code: country,
+            postal_code: Some(postal_code.into()),
+            state: state.map(Into::into),
+            phone: Some("+1 408 967 1000".into()),
+            normalization_status: None,
+            _type: None,
+            extra: Default::default(),
+        }
+    }
+
+    fn address_problems(address: &Address) -> Vec<(String, Problem)> {
+        match address.validate() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .errors()
+                .iter()
+                .map(|error| (error.field.clone(), error.problem.clone()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_valid_addresses() {
+        let valid = [
+            address(CountryCode::UnitedStates, Some("CA"), "95131-1234"),
+            address(CountryCode::Canada, Some("ON"), "K1A 0B1"),
+            address(CountryCode::Canada, Some("ON"), "k1a0b1"),
+            address(CountryCode::UnitedKingdom, None, "SW1A 1AA"),
+            address(CountryCode::Netherlands, None, "1012 AB"),
+            address(CountryCode::Japan, Some("Tokyo"), "100-0001"),
+            address(CountryCode::Germany, None, "10115"),
+            address(CountryCode::Greece, None, "any format"),
+        ];
+        for address in &valid {
+            assert_eq!(Vec::<(String, Problem)>::new(), address_problems(address));
+        }
+    }
+
+    #[test]
+    fn test_invalid_addresses() {
+        let mut invalid = address(CountryCode::Brazil, None, "0131-000");
+        invalid.line1 = " ".into();
+        invalid.phone = Some("011-862-212345678".into());
+        assert_eq!(
+            vec![
+                ("line1".to_string(), Problem::Required),
+                ("state".to_string(), Problem::Required),
+                ("postal_code".to_string(), Problem::Format),
+                ("phone".to_string(), Problem::Format),
+            ],
+            address_problems(&invalid)
+        );
+
+        let mut invalid = address("XX".into(), Some(&"S".repeat(41)), "12345");
+        invalid.phone = Some(format!("+1 {}", "1".repeat(50)));
+        assert_eq!(
+            vec![
+                ("country_code".to_string(), Problem::UnknownCountry),
+                ("state".to_string(), Problem::TooLong(40)),
+                ("phone".to_string(), Problem::TooLong(50)),
+            ],
+            address_problems(&invalid)
+        );
+    }
+
+    #[test]
+    fn test_e123() {
+        assert!(is_e123("+31 42 123 4567"));
+        assert!(is_e123("(042) 123 4567"));
+        assert!(!is_e123("+31 (42) 123 4567"));
+        assert!(!is_e123("+31  42"));
+        assert!(!is_e123("042-123-4567"));
+    }
+
+    #[test]
+    fn test_card_billing_address() {
+        let mut card = card("4417119669820331", CardBrand::Visa, "874");
+        card.billing_address = Some(address(CountryCode::UnitedStates, None, "95131"));
+        assert_eq!(
+            vec![("billing_address.state".to_string(), Problem::Required)],
+            problems(&card)
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let errors = card("4417119669820332", CardBrand::Amex, "87")
+            .validate_on(NaiveDate::from_ymd_opt(2026, 10, 19).unwrap())
+            .unwrap_err();
+        assert_eq!(
+            "number fails the checksum, type does not match the visa card number, cvv2 has the wrong length",
+            errors.to_string()
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crate::client::Client;
+use crate::request::Resp;
+use crate::types::{CreatedWebProfile, PatchOp, WebProfile};
+
+/// Creates a web experience profile. PayPal answers with only the `id` of the new profile, which
+/// `RequestNewPayment::with_experience_profile` applies to a payment.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, web_profile, Client};
+/// use paypal::{Presentation, WebProfile};
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let profile = WebProfile {
+///     name: "mystorefront".to_string(),
+///     temporary: Some(false),
+///     presentation: Some(Presentation {
+///         brand_name: Some("My Storefront".to_string()),
+///         ..Default::default()
+///     }),
+///     ..Default::default()
+/// };
+/// let created = web_profile::create(&Client::new(&token.access_token), &profile).unwrap();
+/// ```
+pub fn create(client: &Client, profile: &WebProfile) -> Resp<CreatedWebProfile> {
+    crate::request::post_json(
+        client,
+        &client.endpoint("/v1/payment-experience/web-profiles"),
+        profile,
+    )
+}
+
+/// Lists the latest 20 web experience profiles of the merchant.
+pub fn list(client: &Client) -> Resp<Vec<WebProfile>> {
+    crate::request::get(
+        client,
+        &client.endpoint("/v1/payment-experience/web-profiles"),
+    )
+}
+
+/// Shows details for a web experience profile, by ID.
+pub fn get(client: &Client, profile_id: &str) -> Resp<WebProfile> {
+    crate::request::get(client, &client.endpoint(&profile_path(profile_id)))
+}
+
+/// Replaces a web experience profile, by ID.
+pub fn update(client: &Client, profile_id: &str, profile: &WebProfile) -> Resp<()> {
+    crate::request::put_json(client, &client.endpoint(&profile_path(profile_id)), profile)
+}
+
+/// Partially updates a web experience profile, by ID.
+pub fn patch(client: &Client, profile_id: &str, ops: &[PatchOp]) -> Resp<()> {
+    crate::request::patch_json(client, &client.endpoint(&profile_path(profile_id)), ops)
+}
+
+/// Deletes a web experience profile, by ID.
+pub fn delete(client: &Client, profile_id: &str) -> Resp<()> {
+    crate::request::delete(client, &client.endpoint(&profile_path(profile_id)))
+}
+
+fn profile_path(profile_id: &str) -> String {
+    format!("/v1/payment-experience/web-profiles/{}", profile_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        FlowConfig, PatchPath, PaymentIntent, PaymentMethod, RequestNewPayment, Transaction,
+    };
+
+    #[test]
+    fn test_web_profile_serialization() {
+        let profile = WebProfile {
+            name: "mystorefront".to_string(),
+            flow_config: Some(FlowConfig {
+                landing_page_type: Some("billing".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            r#"{"name":"mystorefront","flow_config":{"landing_page_type":"billing"}}"#,
+            serde_json::to_string(&profile).unwrap()
+        )
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let created: CreatedWebProfile =
+            serde_json::from_str(r#"{"id":"XP-CP6S-W9DY-96H8-MVN2"}"#).unwrap();
+        let body = RequestNewPayment::new(
+            "mysite.com/return",
+            "mysite.com/cancel",
+            PaymentMethod::Paypal,
+            PaymentIntent::Sale,
+            vec![Transaction::default()],
+        )
+        .with_experience_profile(&created.id);
+        let body = serde_json::to_value(&body).unwrap();
+        assert_eq!("XP-CP6S-W9DY-96H8-MVN2", body["experience_profile_id"]);
+    }
+
+    #[test]
+    fn test_patch_serialization() {
+        let ops = [
+            PatchOp::replace(
+                PatchPath::root().field("presentation").field("brand_name"),
+                "My Storefront",
+            )
+            .unwrap(),
+            PatchOp::remove("/flow_config/landing_page_type"),
+        ];
+        assert_eq!(
+            r#"[{"op":"replace","path":"/presentation/brand_name","value":"My Storefront"},{"op":"remove","path":"/flow_config/landing_page_type"}]"#,
+            serde_json::to_string(&ops).unwrap()
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use crate::client::Client;
+use crate::request::Resp;
+use crate::types::{VerificationStatus, WebhookSignature, WebhookVerification};
+
+/// Asks PayPal whether a webhook event was really sent by PayPal, using the signature in the
+/// headers it was delivered with. Only act on events whose status is `Success`.
+///
+/// ```rust,no_run
+/// use paypal::{get_token, webhook, Client, VerificationStatus, WebhookSignature};
+/// use std::collections::HashMap;
+/// # let headers: HashMap<String, String> = HashMap::new();
+/// # let body = "{}";
+///
+/// let token = get_token("my_id", "my_secret").unwrap();
+/// let event: serde_json::Value = serde_json::from_str(body).unwrap();
+/// let signature = WebhookSignature::from_headers(&headers, "1JE4291016473214C", event).unwrap();
+/// let status = webhook::verify(&Client::new(&token.access_token), &signature).unwrap();
+/// if status == VerificationStatus::Success {
+///     // handle the event
+/// }
+/// ```
+pub fn verify(client: &Client, signature: &WebhookSignature) -> Resp<VerificationStatus> {
+    let verification: WebhookVerification = crate::request::post_json(
+        client,
+        &client.endpoint("/v1/notifications/verify-webhook-signature"),
+        signature,
+    )?;
+    Ok(verification.verification_status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::transport::{HttpRequest, HttpResponse, Transport};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    // accepts only the signature of the event below
+    struct Paypal;
+
+    impl Transport for Paypal {
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse, Error> {
+            assert!(request
+                .url
+                .ends_with("/v1/notifications/verify-webhook-signature"));
+            let body: serde_json::Value =
+                serde_json::from_slice(request.body.as_deref().unwrap()).unwrap();
+            assert_eq!("WH-COC11055RA711503B", body["webhook_event"]["id"]);
+            let status = if body["transmission_sig"] == "valid" {
+                "SUCCESS"
+            } else {
+                "FAILURE"
+            };
+            Ok(HttpResponse {
+                status: 200,
+                headers: Default::default(),
+                body: json!({ "verification_status": status })
+                    .to_string()
+                    .into_bytes(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let client = Client::new("token").with_transport(Paypal);
+        let mut headers: HashMap<String, String> = [
+            ("Paypal-Auth-Algo", "SHA256withRSA"),
+            (
+                "Paypal-Cert-Url",
+                "https://api.paypal.com/v1/notifications/certs/CERT",
+            ),
+            (
+                "Paypal-Transmission-Id",
+                "69cd13f0-d67a-11e5-baa3-778b53f4ae55",
+            ),
+            ("Paypal-Transmission-Sig", "valid"),
+            ("Paypal-Transmission-Time", "2016-02-18T20:01:35Z"),
+        ]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        let event = json!({ "id": "WH-COC11055RA711503B", "event_type": "PAYMENT.SALE.COMPLETED" });
+        let signature =
+            WebhookSignature::from_headers(&headers, "1JE4291016473214C", event.clone());
+        assert_eq!(
+            VerificationStatus::Success,
+            verify(&client, &signature.unwrap()).unwrap()
+        );
+
+        headers.insert("Paypal-Transmission-Sig".into(), "forged".into());
+        let signature =
+            WebhookSignature::from_headers(&headers, "1JE4291016473214C", event.clone());
+        assert_eq!(
+            VerificationStatus::Failure,
+            verify(&client, &signature.unwrap()).unwrap()
+        );
+
+        headers.remove("Paypal-Cert-Url");
+        assert!(WebhookSignature::from_headers(&headers, "1JE4291016473214C", event).is_err());
+    }
+}